@@ -0,0 +1,271 @@
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+
+use crate::charset::{lookup, Code, ESC, GSM7_CHARSET, GSM7_EXTENSION};
+#[cfg(feature = "alloc")]
+use crate::pack::{septet_at, septet_count, Packer};
+#[cfg(feature = "alloc")]
+use crate::{Gsm7Error, Padding};
+
+#[cfg(feature = "alloc")]
+const CR: u8 = 0x0D;
+
+/// A national language with tables in 3GPP 23.038, numbered as it numbers them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum NationalLanguage {
+    Turkish = 1,
+    /// Has a single shift table only.
+    Spanish = 2,
+    Portuguese = 3,
+}
+
+impl NationalLanguage {
+    fn locking_shift_table(self) -> &'static [char; 128] {
+        match self {
+            NationalLanguage::Turkish => &TURKISH_LOCKING_SHIFT,
+            NationalLanguage::Spanish => &GSM7_CHARSET,
+            NationalLanguage::Portuguese => &PORTUGUESE_LOCKING_SHIFT,
+        }
+    }
+
+    fn single_shift_table(self) -> &'static [Option<char>; 128] {
+        match self {
+            NationalLanguage::Turkish => &TURKISH_SINGLE_SHIFT,
+            NationalLanguage::Spanish => &SPANISH_SINGLE_SHIFT,
+            NationalLanguage::Portuguese => &PORTUGUESE_SINGLE_SHIFT,
+        }
+    }
+}
+
+const fn patch_locking(overrides: &[(u8, char)]) -> [char; 128] {
+    let mut table = GSM7_CHARSET;
+    let mut i = 0;
+    while i < overrides.len() {
+        table[overrides[i].0 as usize] = overrides[i].1;
+        i += 1;
+    }
+    table
+}
+
+const fn patch_single(additions: &[(u8, char)]) -> [Option<char>; 128] {
+    let mut table = GSM7_EXTENSION;
+    let mut i = 0;
+    while i < additions.len() {
+        table[additions[i].0 as usize] = Some(additions[i].1);
+        i += 1;
+    }
+    table
+}
+
+const TURKISH_LOCKING_SHIFT: [char; 128] = patch_locking(&[
+    (0x04, '€'), (0x07, 'ı'), (0x0B, 'Ğ'), (0x0C, 'ğ'), (0x1C, 'Ş'), (0x1D, 'ş'), (0x40, 'İ'), (0x60, 'ç'),
+]);
+
+const PORTUGUESE_LOCKING_SHIFT: [char; 128] = patch_locking(&[
+    (0x04, 'ê'), (0x06, 'ú'), (0x07, 'í'), (0x08, 'ó'), (0x09, 'ç'), (0x0B, 'Ô'), (0x0C, 'ô'), (0x0E, 'Á'),
+    (0x0F, 'á'), (0x12, 'ª'), (0x13, 'Ç'), (0x14, 'À'), (0x15, '∞'), (0x16, '^'), (0x17, '\\'), (0x18, '€'),
+    (0x19, 'Ó'), (0x1A, '|'), (0x1C, 'Â'), (0x1D, 'â'), (0x1E, 'Ê'), (0x24, 'º'), (0x40, 'Í'), (0x5B, 'Ã'),
+    (0x5C, 'Õ'), (0x5D, 'Ú'), (0x60, '~'), (0x7B, 'ã'), (0x7C, 'õ'), (0x7D, '`'),
+]);
+
+// The national single shift tables all keep the default extension table's characters.
+const TURKISH_SINGLE_SHIFT: [Option<char>; 128] = patch_single(&[
+    (0x47, 'Ğ'), (0x49, 'İ'), (0x53, 'Ş'), (0x63, 'ç'), (0x67, 'ğ'), (0x69, 'ı'), (0x73, 'ş'),
+]);
+
+const SPANISH_SINGLE_SHIFT: [Option<char>; 128] = patch_single(&[
+    (0x09, 'ç'), (0x41, 'Á'), (0x49, 'Í'), (0x4F, 'Ó'), (0x55, 'Ú'), (0x61, 'á'), (0x69, 'í'), (0x6F, 'ó'),
+    (0x75, 'ú'),
+]);
+
+const PORTUGUESE_SINGLE_SHIFT: [Option<char>; 128] = patch_single(&[
+    (0x05, 'ê'), (0x09, 'ç'), (0x0B, 'Ô'), (0x0C, 'ô'), (0x0E, 'Á'), (0x0F, 'á'), (0x12, 'Φ'), (0x13, 'Γ'),
+    (0x15, 'Ω'), (0x16, 'Π'), (0x17, 'Ψ'), (0x18, 'Σ'), (0x19, 'Θ'), (0x1F, 'Ê'), (0x41, 'À'), (0x49, 'Í'),
+    (0x4F, 'Ó'), (0x55, 'Ú'), (0x5B, 'Ã'), (0x5C, 'Õ'), (0x61, 'Â'), (0x69, 'í'), (0x6F, 'ó'), (0x75, 'ú'),
+    (0x7B, 'ã'), (0x7C, 'õ'), (0x7F, 'â'),
+]);
+
+const LOCKING_SHIFT_LANGUAGES: [Option<NationalLanguage>; 3] =
+    [None, Some(NationalLanguage::Turkish), Some(NationalLanguage::Portuguese)];
+const SINGLE_SHIFT_LANGUAGES: [Option<NationalLanguage>; 4] = [
+    None,
+    Some(NationalLanguage::Turkish),
+    Some(NationalLanguage::Spanish),
+    Some(NationalLanguage::Portuguese),
+];
+
+/// The tables that GSM 7-bit text is encoded with: a locking shift table giving the meaning of
+/// each septet, and a single shift table giving the meaning of a septet after an escape. `None`
+/// means the default alphabet's table. A national table has to be announced to the receiver with
+/// the UDH information elements from `header_elements`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Alphabet {
+    /// Spanish has no locking shift table, so `Some(Spanish)` is the same as `None`.
+    pub locking_shift: Option<NationalLanguage>,
+    pub single_shift: Option<NationalLanguage>,
+}
+
+impl Alphabet {
+    /// The default alphabet and its extension table, which need no header.
+    pub const DEFAULT: Alphabet = Alphabet { locking_shift: None, single_shift: None };
+
+    /// The tables for a BCP 47 language tag such as `tr`, `pt-BR` or `es_ES`, going by its primary
+    /// language subtag: both Turkish tables for Turkish, the Spanish single shift table for
+    /// Spanish, and both Portuguese tables for Portuguese. Any other language gets the default
+    /// alphabet.
+    pub fn for_language(tag: &str) -> Self {
+        let language = tag.split(['-', '_']).next().unwrap_or("");
+        let national = [
+            ("tr", NationalLanguage::Turkish),
+            ("es", NationalLanguage::Spanish),
+            ("pt", NationalLanguage::Portuguese),
+        ]
+        .iter()
+        .find(|(code, _)| language.eq_ignore_ascii_case(code))
+        .map(|&(_, national)| national);
+        let alphabet = match national {
+            Some(NationalLanguage::Spanish) => Alphabet { locking_shift: None, single_shift: national },
+            _ => Alphabet { locking_shift: national, single_shift: national },
+        };
+        #[cfg(feature = "tracing")]
+        tracing::debug!(tag, ?alphabet, "selected GSM 7-bit tables");
+        alphabet
+    }
+
+    /// Every combination of tables, starting with the default alphabet.
+    pub fn all() -> impl Iterator<Item = Alphabet> {
+        LOCKING_SHIFT_LANGUAGES.iter().flat_map(|&locking_shift| {
+            SINGLE_SHIFT_LANGUAGES.iter().map(move |&single_shift| Alphabet { locking_shift, single_shift })
+        })
+    }
+
+    fn locking_shift_table(&self) -> &'static [char; 128] {
+        self.locking_shift.map_or(&GSM7_CHARSET, NationalLanguage::locking_shift_table)
+    }
+
+    fn single_shift_table(&self) -> &'static [Option<char>; 128] {
+        self.single_shift.map_or(&GSM7_EXTENSION, NationalLanguage::single_shift_table)
+    }
+
+    fn code(&self, c: char) -> Code {
+        if *self == Self::DEFAULT {
+            return lookup(c);
+        }
+        let mut locking = self.locking_shift_table().iter().enumerate().filter(|&(septet, _)| septet != ESC as usize);
+        if let Some((septet, _)) = locking.find(|&(_, &table_char)| table_char == c) {
+            return Code::Basic(septet as u8);
+        }
+        match self.single_shift_table().iter().position(|&table_char| table_char == Some(c)) {
+            Some(septet) => Code::Escaped(septet as u8),
+            None => Code::Unencodable,
+        }
+    }
+
+    /// Septets `c` takes in these tables, or `None` if it has no encoding in them.
+    pub fn septets(&self, c: char) -> Option<usize> {
+        self.code(c).septets()
+    }
+
+    /// The UDH information elements announcing the national tables, as the element identifier, its
+    /// length and the language: 0x25 for the locking shift table and 0x24 for the single shift
+    /// table.
+    pub fn header_elements(&self) -> impl Iterator<Item = [u8; 3]> {
+        let locking_shift = self.locking_shift.filter(|&language| language != NationalLanguage::Spanish);
+        let locking_shift = locking_shift.map(|language| [0x25, 0x01, language as u8]);
+        let single_shift = self.single_shift.map(|language| [0x24, 0x01, language as u8]);
+        locking_shift.into_iter().chain(single_shift)
+    }
+
+    /// Encodes `s` with these tables, filling the spare bits of the final byte as `padding` says,
+    /// the same way `Encoder::finish` does.
+    #[cfg(feature = "alloc")]
+    pub fn encode(&self, s: &str, padding: Padding) -> Result<Vec<u8>, Gsm7Error> {
+        let mut packer = Packer::new();
+        let mut out = Vec::with_capacity(s.len());
+        for c in s.chars() {
+            match self.code(c) {
+                Code::Basic(septet) => out.extend(packer.push(septet)),
+                Code::Escaped(septet) => {
+                    out.extend(packer.push(ESC));
+                    out.extend(packer.push(septet));
+                },
+                Code::Unencodable => return Err(Gsm7Error::Unencodable(c)),
+            }
+        }
+        if padding == Padding::Cr {
+            match packer.pending_bits() {
+                1 => out.extend(packer.push(CR)),
+                0 if s.ends_with('\r') => out.extend(packer.push(CR)),
+                _ => (),
+            }
+        }
+        out.extend(packer.finish_zeroed());
+        Ok(out)
+    }
+
+    /// Decodes packed `bytes`, or their first `udl` septets, with these tables.
+    #[cfg(feature = "alloc")]
+    pub fn decode(&self, bytes: &[u8], udl: Option<usize>) -> Result<String, Gsm7Error> {
+        let end = udl.unwrap_or_else(|| septet_count(bytes.len()));
+        let mut out = String::with_capacity(end);
+        let mut i = 0;
+        while i < end {
+            let septet = septet_at(bytes, i).ok_or(Gsm7Error::UnexpectedEnd)?;
+            i += 1;
+            if septet != ESC {
+                out.push(self.locking_shift_table()[septet as usize]);
+                continue;
+            }
+            if i == end {
+                return Err(Gsm7Error::TruncatedEscape);
+            }
+            let septet = septet_at(bytes, i).ok_or(Gsm7Error::UnexpectedEnd)?;
+            i += 1;
+            out.push(self.single_shift_table()[septet as usize].ok_or(Gsm7Error::InvalidEscape(septet))?);
+        }
+        Ok(out)
+    }
+}
+
+/// Any combination of tables, as `Alphabet::all` has them.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Alphabet {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Alphabet {
+            locking_shift: *u.choose(&LOCKING_SHIFT_LANGUAGES)?,
+            single_shift: *u.choose(&SINGLE_SHIFT_LANGUAGES)?,
+        })
+    }
+}
+
+/// How well one combination of tables covers a text, from `coverage`.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Coverage {
+    pub alphabet: Alphabet,
+    /// Characters with no encoding in these tables.
+    pub uncovered: usize,
+    /// Septets the other characters take, not counting the header announcing the tables.
+    pub septets: usize,
+}
+
+/// How well each combination of tables in `Alphabet::all` covers `s`, in that order. The
+/// alphabets with nothing uncovered can encode `s`, and the one with the fewest septets, once its
+/// header is allowed for, is the cheapest.
+#[cfg(feature = "alloc")]
+pub fn coverage(s: &str) -> Vec<Coverage> {
+    Alphabet::all()
+        .map(|alphabet| {
+            let (uncovered, septets) = s.chars().fold((0, 0), |(uncovered, septets), c| match alphabet.septets(c) {
+                Some(n) => (uncovered, septets + n),
+                None => (uncovered + 1, septets),
+            });
+            Coverage { alphabet, uncovered, septets }
+        })
+        .collect()
+}
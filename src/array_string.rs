@@ -0,0 +1,121 @@
+use core::fmt;
+
+use crate::charset::{lookup, Code, ESC};
+use crate::pack::byte_count;
+use crate::{Chars, Gsm7Error};
+
+/// GSM 7-bit text packed into an `N`-byte array as it is written, for holding a message without
+/// an allocator. Holds up to `N * 8 / 7` septets.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Gsm7ArrayString<const N: usize> {
+    bytes: [u8; N],
+    septets: usize,
+}
+
+impl<const N: usize> Gsm7ArrayString<N> {
+    /// The number of septets the string can hold.
+    pub const CAPACITY: usize = N * 8 / 7;
+
+    pub const fn new() -> Self {
+        Self { bytes: [0; N], septets: 0 }
+    }
+
+    /// Length of the string when encoded, in septets.
+    pub fn septet_len(&self) -> usize {
+        self.septets
+    }
+
+    /// The packed text, with the spare bits of the final byte zero. Whether seven spare bits are
+    /// text can only be told from the septet length, so send it as the UDL.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..byte_count(self.septets)]
+    }
+
+    pub fn chars(&self) -> Chars<'_> {
+        Chars::with_udl(&self.bytes, self.septets)
+    }
+
+    /// Decodes the text into `out` as UTF-8, returning the number of bytes written.
+    pub fn decode_into(&self, out: &mut [u8]) -> Result<usize, Gsm7Error> {
+        let mut len = 0;
+        for c in self.chars() {
+            let c = c?;
+            let buf = out.get_mut(len..len + c.len_utf8()).ok_or(Gsm7Error::BufferTooSmall)?;
+            len += c.encode_utf8(buf).len();
+        }
+        Ok(len)
+    }
+
+    /// Appends `c`, failing and leaving the string as it was if it has no GSM 7-bit encoding or
+    /// there is no room for it.
+    pub fn push(&mut self, c: char) -> Result<(), Gsm7Error> {
+        match lookup(c) {
+            Code::Basic(b) => {
+                if self.septets + 1 > Self::CAPACITY {
+                    return Err(Gsm7Error::BufferTooSmall);
+                }
+                self.put(b);
+            },
+            Code::Escaped(b) => {
+                if self.septets + 2 > Self::CAPACITY {
+                    return Err(Gsm7Error::BufferTooSmall);
+                }
+                self.put(ESC);
+                self.put(b);
+            },
+            Code::Unencodable => return Err(Gsm7Error::Unencodable(c)),
+        }
+        Ok(())
+    }
+
+    /// Appends `s`, failing and leaving the string as it was if any of it has no GSM 7-bit
+    /// encoding or there is no room for all of it.
+    pub fn push_str(&mut self, s: &str) -> Result<(), Gsm7Error> {
+        let mut septets = self.septets;
+        for c in s.chars() {
+            septets += lookup(c).septets().ok_or(Gsm7Error::Unencodable(c))?;
+        }
+        if septets > Self::CAPACITY {
+            return Err(Gsm7Error::BufferTooSmall);
+        }
+        for c in s.chars() {
+            self.push(c)?;
+        }
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.bytes = [0; N];
+        self.septets = 0;
+    }
+
+    fn put(&mut self, septet: u8) {
+        let bit = self.septets * 7;
+        let (byte, shift) = (bit / 8, bit % 8);
+        self.bytes[byte] |= septet << shift;
+        if shift > 1 {
+            self.bytes[byte + 1] |= septet >> (8 - shift);
+        }
+        self.septets += 1;
+    }
+}
+
+impl<const N: usize> Default for Gsm7ArrayString<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Decodes the text as it is written.
+impl<const N: usize> fmt::Display for Gsm7ArrayString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Only valid text is ever pushed, so decoding can't fail.
+        self.chars().flatten().try_for_each(|c| fmt::Write::write_char(f, c))
+    }
+}
+
+impl<const N: usize> fmt::Debug for Gsm7ArrayString<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Gsm7ArrayString").field("bytes", &self.as_bytes()).field("septets", &self.septets).finish()
+    }
+}
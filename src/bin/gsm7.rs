@@ -0,0 +1,249 @@
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::{Parser, Subcommand};
+use gsm7::{copy_decode, copy_encode, explain_bytes, pdu, Gsm7Reader, HexReader, HexWriter, Issue, Septets};
+
+/// Encode and decode GSM 7-bit packed data.
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Encode UTF-8 text as packed septets.
+    Encode {
+        /// Write the packed data as hex.
+        #[arg(long)]
+        hex: bool,
+        /// Text to encode. Read from stdin if omitted, including any trailing newline.
+        text: Option<String>,
+    },
+    /// Decode packed septets to UTF-8 text.
+    Decode {
+        /// Read the packed data as hex, ignoring whitespace.
+        #[arg(long)]
+        hex: bool,
+        /// Decode exactly this many septets.
+        #[arg(long)]
+        udl: Option<usize>,
+        /// Packed data to decode. Read from stdin if omitted.
+        data: Option<String>,
+    },
+    /// Print a septet-by-septet breakdown of packed data.
+    Explain {
+        /// Treat septets after the first `udl` as padding.
+        #[arg(long)]
+        udl: Option<usize>,
+        /// Packed data as hex, ignoring whitespace. Read from stdin if omitted.
+        data: Option<String>,
+    },
+    /// Print a hex SMS-SUBMIT PDU for each part of a message, ready for AT+CMGS.
+    Segment {
+        /// Destination number, with a leading + if international.
+        #[arg(long)]
+        to: String,
+        /// Read the text from this file, less one trailing newline.
+        #[arg(long, conflicts_with = "text")]
+        text_file: Option<PathBuf>,
+        /// Concatenation reference. Derived from the clock if omitted.
+        #[arg(long)]
+        reference: Option<u8>,
+        /// Text to send. Read from stdin if neither this nor --text-file is given.
+        text: Option<String>,
+    },
+    /// Print the sender and text of each SMS-DELIVER PDU in a modem log read from stdin, such as
+    /// a minicom capture.
+    Log,
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse().command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("gsm7: {}", e);
+            ExitCode::FAILURE
+        },
+    }
+}
+
+fn run(command: Command) -> io::Result<()> {
+    let stdin = io::stdin();
+    let stdout = BufWriter::new(io::stdout().lock());
+    match command {
+        Command::Encode { hex, text } => {
+            let input = input(text, &stdin);
+            if hex {
+                let mut out = HexWriter::new(stdout);
+                copy_encode(input, &mut out)?;
+                writeln!(out.get_mut())?;
+                out.get_mut().flush()
+            }
+            else {
+                copy_encode(input, stdout).map(drop)
+            }
+        },
+        Command::Decode { hex, udl, data } => {
+            let input = input(data, &stdin);
+            if hex {
+                decode(HexReader::new(input), udl, stdout)
+            }
+            else {
+                decode(input, udl, stdout)
+            }
+        },
+        Command::Explain { udl, data } => {
+            let mut bytes = Vec::new();
+            HexReader::new(input(data, &stdin)).read_to_end(&mut bytes)?;
+            let mut out = stdout;
+            explain(&bytes, udl, &mut out)?;
+            out.flush()
+        },
+        Command::Segment { to, text_file, reference, text } => {
+            let text = match (text, text_file) {
+                (Some(text), _) => text,
+                (None, Some(path)) => trim_newline(std::fs::read_to_string(path)?),
+                (None, None) => trim_newline(io::read_to_string(stdin)?),
+            };
+            let reference = reference.unwrap_or_else(clock_reference);
+            let pdus = pdu::submit_pdus(&to, &text, reference)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e.to_string()))?;
+            let mut out = HexWriter::new(stdout);
+            for pdu in pdus {
+                // An empty SMSC address, so the modem uses its default.
+                out.write_all(&[0x00])?;
+                out.write_all(&pdu)?;
+                writeln!(out.get_mut())?;
+            }
+            out.get_mut().flush()
+        },
+        Command::Log => {
+            let mut out = stdout;
+            // Decode the lines as they arrive, so that a live log prints each message as it comes,
+            // and stop at the first read error.
+            let mut read_error = None;
+            let lines = stdin.lock().lines().map_while(|line| line.map_err(|e| read_error = Some(e)).ok());
+            for message in pdu::decode_log(lines) {
+                match message {
+                    Ok(message) => writeln!(out, "{}: {}", message.originator, message.user_data.text)?,
+                    // Keep going, so that one garbled PDU doesn't hide the rest of the log.
+                    Err(e) => eprintln!("gsm7: {}", e),
+                }
+                out.flush()?;
+            }
+            read_error.map_or(Ok(()), Err)
+        },
+    }
+}
+
+// Writes a line per septet of `bytes` giving its bit range, value and meaning, then any padding.
+fn explain<W: Write>(bytes: &[u8], udl: Option<usize>, out: &mut W) -> io::Result<()> {
+    let total = bytes.len() * 8 / 7;
+    writeln!(out, "septet  bits     value  meaning")?;
+    let mut text = 0;
+    for annotation in explain_bytes(bytes, udl) {
+        let (start, end) = (annotation.bits.start / 7, annotation.bits.end / 7);
+        let meaning = match (annotation.char, annotation.issue) {
+            (_, Some(Issue::UnexpectedEnd)) => {
+                writeln!(out, "UDL is {} septets but the data holds only {}", end, start)?;
+                continue;
+            },
+            (None, Some(Issue::TruncatedEscape)) => "escape with nothing after it".to_owned(),
+            (Some(c), Some(Issue::InvalidEscape)) => format!("{:?} from the basic table, as the escape is invalid", c),
+            (Some(c), Some(Issue::PossiblePadding)) => format!("{:?}, or padding; pass --udl to be sure", c),
+            (Some(c), _) => format!("{:?}", c),
+            (None, _) => "undecodable".to_owned(),
+        };
+        if end - start == 2 {
+            line(out, start, annotation.septets[0], "escape")?;
+        }
+        line(out, end - 1, annotation.septets[end - start - 1], &meaning)?;
+        text = end;
+    }
+    for (i, septet) in Septets::new(bytes, total).enumerate().skip(text) {
+        line(out, i, septet?, "padding beyond the UDL")?;
+    }
+    let bits = bytes.len() * 8 - total * 7;
+    if bits > 0 {
+        let value = bytes[bytes.len() - 1] >> (8 - bits);
+        let meaning = if value == 0 { "zero fill" } else { "nonzero fill" };
+        writeln!(out, "{:>6}  {:<7}  {:#04x}   {} padding bits, {}", "-", bit_range(total * 7, bits), value, bits, meaning)?;
+    }
+    Ok(())
+}
+
+fn line<W: Write>(out: &mut W, i: usize, septet: u8, meaning: &str) -> io::Result<()> {
+    writeln!(out, "{:>6}  {:<7}  {:#04x}   {}", i, bit_range(i * 7, 7), septet, meaning)
+}
+
+fn bit_range(start: usize, len: usize) -> String {
+    format!("{}-{}", start, start + len - 1)
+}
+
+fn trim_newline(mut text: String) -> String {
+    if text.ends_with('\n') {
+        text.pop();
+        if text.ends_with('\r') {
+            text.pop();
+        }
+    }
+    text
+}
+
+fn clock_reference() -> u8 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u8)
+}
+
+fn input<'a>(arg: Option<String>, stdin: &'a io::Stdin) -> Box<dyn BufRead + 'a> {
+    match arg {
+        Some(arg) => Box::new(io::Cursor::new(arg.into_bytes())),
+        None => Box::new(stdin.lock()),
+    }
+}
+
+fn decode<R: Read, W: Write>(input: R, udl: Option<usize>, mut out: W) -> io::Result<()> {
+    match udl {
+        Some(udl) => {
+            let mut buf = [0; 4];
+            for c in Gsm7Reader::with_udl(BufReader::new(input), udl) {
+                out.write_all(c?.encode_utf8(&mut buf).as_bytes())?;
+            }
+            out.flush()
+        },
+        None => copy_decode(input, out).map(drop),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::explain;
+
+    #[test]
+    fn explain_shows_escapes_and_padding() {
+        let mut out = Vec::new();
+        // "{Hello", whose seven septets leave seven zero bits that read as '@'.
+        explain(&[0x1B, 0x14, 0xB2, 0xCC, 0x66, 0xBF, 0x01], None, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines = out.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 9);
+        assert_eq!(lines[1], "     0  0-6      0x1b   escape");
+        assert_eq!(lines[2], "     1  7-13     0x28   '{'");
+        assert_eq!(lines[8], "     7  49-55    0x00   '@', or padding; pass --udl to be sure");
+
+        let mut out = Vec::new();
+        explain(&[0xC8, 0x34], None, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.ends_with("     -  14-15    0x00   2 padding bits, zero fill\n"));
+
+        let mut out = Vec::new();
+        explain(&[0x1B, 0x14], Some(1), &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("escape with nothing after it"));
+        assert!(out.contains("padding beyond the UDL"));
+    }
+}
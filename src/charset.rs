@@ -0,0 +1,374 @@
+#[cfg(any(feature = "proptest", feature = "quickcheck"))]
+use alloc::vec::Vec;
+
+use crate::Gsm7Error;
+
+pub(crate) const ESC: u8 = 0x1B;
+
+/// The default alphabet, indexed by septet. Septet 0x1B is the escape to the extension table
+/// rather than a character, and is shown as `'\x1B'`.
+pub const GSM7_CHARSET: [char; 128] = [
+    '@', '£', '$', '¥', 'è', 'é', 'ù', 'ì',  'ò', 'Ç', '\n', 'Ø',    'ø', '\r', 'Å', 'å',
+    'Δ', '_', 'Φ', 'Γ', 'Λ', 'Ω', 'Π', 'Ψ',  'Σ', 'Θ', 'Ξ',  '\x1B', 'Æ', 'æ',  'ß', 'É',
+    ' ', '!', '"', '#', '¤', '%', '&', '\'', '(', ')', '*',  '+',    ',', '-',  '.', '/',
+    '0', '1', '2', '3', '4', '5', '6', '7', '8',  '9', ':',  ';',    '<', '=',  '>', '?',
+    '¡', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H',  'I', 'J',  'K',    'L', 'M',  'N', 'O',
+    'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X',  'Y', 'Z',  'Ä',    'Ö', 'Ñ',  'Ü', '§',
+    '¿', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h',  'i', 'j',  'k',    'l', 'm',  'n', 'o',
+    'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x',  'y', 'z',  'ä',    'ö', 'ñ',  'ü', 'à',
+];
+
+// Whether each ASCII character is encoded as the septet of the same value, which is true of most
+// printable ASCII.
+#[cfg(feature = "std")]
+pub(crate) const ASCII_IDENTITY: [bool; 128] = ascii_identity();
+
+#[cfg(feature = "std")]
+const fn ascii_identity() -> [bool; 128] {
+    let mut table = [false; 128];
+    let mut i = 0;
+    while i < table.len() {
+        table[i] = i != ESC as usize && GSM7_CHARSET[i] as usize == i;
+        i += 1;
+    }
+    table
+}
+
+/// The default extension table, as each character and the septet that follows the escape.
+pub const GSM7_EXTENSION_CHARS: [(char, u8); 10] = [
+    ('\x0C', 0x0A), ('[', 0x3C), ('\\', 0x2F), (']', 0x3E), ('^', 0x14),
+    ('{', 0x28),    ('|', 0x40), ('}', 0x29),  ('~', 0x3D), ('€', 0x65),
+];
+
+/// The characters of the default extension table, each of which takes two septets: an escape and
+/// the character's own septet.
+pub const EXTENSION_CHARS: [char; 10] = extension_chars();
+
+const fn extension_chars() -> [char; 10] {
+    let mut chars = ['\0'; 10];
+    let mut i = 0;
+    while i < chars.len() {
+        chars[i] = GSM7_EXTENSION_CHARS[i].0;
+        i += 1;
+    }
+    chars
+}
+
+/// The table a character was looked up in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Table {
+    Basic,
+    Extension,
+}
+
+impl Table {
+    /// Septets a character from this table takes, counting the escape for the extension table.
+    pub fn septets(self) -> usize {
+        match self {
+            Table::Basic => 1,
+            Table::Extension => 2,
+        }
+    }
+}
+
+/// An encodable character, from `supported_chars`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SupportedChar {
+    pub char: char,
+    /// The character's septet, which follows an escape for the extension table.
+    pub septet: u8,
+    pub table: Table,
+}
+
+impl SupportedChar {
+    /// Septets the character takes when encoded.
+    pub fn septets(&self) -> usize {
+        self.table.septets()
+    }
+}
+
+/// Every encodable character: the basic table in septet order, then the extension table in
+/// septet order.
+pub fn supported_chars() -> impl Iterator<Item = SupportedChar> {
+    let basic = (0..128u8)
+        .filter(|&septet| septet != ESC)
+        .map(|septet| SupportedChar { char: basic_char(septet), septet, table: Table::Basic });
+    let extension = (0..128u8)
+        .filter_map(|septet| Some(SupportedChar { char: extension_char(septet)?, septet, table: Table::Extension }));
+    basic.chain(extension)
+}
+
+// Bitmaps of the characters below U+0400 in each table, which is all of them but the euro sign.
+const BITMAP_CHARS: usize = 0x400;
+const BASIC_BITMAP: [u8; BITMAP_CHARS / 8] = basic_bitmap();
+const EXTENSION_BITMAP: [u8; BITMAP_CHARS / 8] = extension_bitmap();
+
+const fn basic_bitmap() -> [u8; BITMAP_CHARS / 8] {
+    let mut bitmap = [0; BITMAP_CHARS / 8];
+    let mut i = 0;
+    while i < GSM7_CHARSET.len() {
+        let c = GSM7_CHARSET[i] as usize;
+        assert!(c < BITMAP_CHARS);
+        if i != ESC as usize {
+            bitmap[c / 8] |= 1 << (c % 8);
+        }
+        i += 1;
+    }
+    bitmap
+}
+
+const fn extension_bitmap() -> [u8; BITMAP_CHARS / 8] {
+    let mut bitmap = [0; BITMAP_CHARS / 8];
+    let mut i = 0;
+    while i < GSM7_EXTENSION_CHARS.len() {
+        let c = GSM7_EXTENSION_CHARS[i].0 as usize;
+        if c != '€' as usize {
+            assert!(c < BITMAP_CHARS);
+            bitmap[c / 8] |= 1 << (c % 8);
+        }
+        i += 1;
+    }
+    bitmap
+}
+
+fn in_bitmap(bitmap: &[u8; BITMAP_CHARS / 8], c: char) -> bool {
+    let c = c as usize;
+    c < BITMAP_CHARS && bitmap[c / 8] & 1 << (c % 8) != 0
+}
+
+/// Whether `c` is in the basic table, and so takes one septet. A bit test, with no table search.
+pub fn is_basic_char(c: char) -> bool {
+    in_bitmap(&BASIC_BITMAP, c)
+}
+
+/// Whether `c` is in the default extension table, and so takes two septets.
+pub fn is_extension_char(c: char) -> bool {
+    c == '€' || in_bitmap(&EXTENSION_BITMAP, c)
+}
+
+/// Whether `c` has a GSM 7-bit encoding in either table.
+pub fn is_gsm7_char(c: char) -> bool {
+    is_basic_char(c) || is_extension_char(c)
+}
+
+/// Whether `s` has a character with no GSM 7-bit encoding, so that it has to be sent as UCS-2.
+pub fn requires_ucs2(s: &str) -> bool {
+    !s.chars().all(is_gsm7_char)
+}
+
+/// The first character of `s` with no GSM 7-bit encoding, from `first_non_gsm7_char`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NonGsm7Char {
+    /// The byte offset of the character in the text.
+    pub offset: usize,
+    pub char: char,
+}
+
+impl NonGsm7Char {
+    /// Whether the character is outside the Basic Multilingual Plane, as most emoji are. UCS-2
+    /// can't hold it, so it is sent as a UTF-16 surrogate pair, which takes two of the 70 or 67
+    /// characters of a part, and which older handsets show as two unknown characters.
+    pub fn is_astral(&self) -> bool {
+        self.char > '\u{FFFF}'
+    }
+
+    /// Whether the character is in one of the blocks emoji come from: the symbols and
+    /// pictographs, emoticons, transport, dingbats and miscellaneous symbols blocks, or is a
+    /// regional indicator, a variation selector or a zero-width joiner.
+    pub fn is_emoji(&self) -> bool {
+        matches!(
+            self.char,
+            '\u{1F000}'..='\u{1FAFF}' | '\u{2600}'..='\u{27BF}' | '\u{FE00}'..='\u{FE0F}' | '\u{200D}'
+        )
+    }
+}
+
+/// The first character of `s` with no GSM 7-bit encoding, if any, so that a sender can warn that
+/// the text will go as UCS-2, and whether that is because of an emoji.
+pub fn first_non_gsm7_char(s: &str) -> Option<NonGsm7Char> {
+    s.char_indices().find(|&(_, c)| !is_gsm7_char(c)).map(|(offset, char)| NonGsm7Char { offset, char })
+}
+
+/// The default extension table indexed by the septet that follows the escape.
+pub const GSM7_EXTENSION: [Option<char>; 128] = by_septet(&GSM7_EXTENSION_CHARS);
+
+const fn by_septet(chars: &[(char, u8)]) -> [Option<char>; 128] {
+    let mut table = [None; 128];
+    let mut i = 0;
+    while i < chars.len() {
+        table[chars[i].1 as usize] = Some(chars[i].0);
+        i += 1;
+    }
+    table
+}
+
+// The basic table encoded as UTF-8, padded to four bytes, and the length of each encoding.
+#[cfg(feature = "alloc")]
+pub(crate) const GSM7_UTF8: [([u8; 4], u8); 128] = utf8_table();
+
+#[cfg(feature = "alloc")]
+const fn utf8_table() -> [([u8; 4], u8); 128] {
+    let mut table = [([0; 4], 0); 128];
+    let mut i = 0;
+    while i < table.len() {
+        let len = GSM7_CHARSET[i].encode_utf8(&mut table[i].0).len();
+        table[i].1 = len as u8;
+        i += 1;
+    }
+    table
+}
+
+pub(crate) fn basic_char(septet: u8) -> char {
+    GSM7_CHARSET[(septet & 0x7F) as usize]
+}
+
+pub(crate) fn extension_char(septet: u8) -> Option<char> {
+    GSM7_EXTENSION.get(septet as usize).copied().flatten()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Code {
+    Basic(u8),
+    Escaped(u8),
+    Unencodable,
+}
+
+impl Code {
+    pub(crate) fn septets(self) -> Option<usize> {
+        match self {
+            Code::Basic(_) => Some(1),
+            Code::Escaped(_) => Some(2),
+            Code::Unencodable => None,
+        }
+    }
+}
+
+// Every encodable character with its code, sorted by character so it can be binary searched.
+pub(crate) const GSM7_BY_CHAR: [(char, Code); 137] = by_char();
+
+const fn by_char() -> [(char, Code); 137] {
+    let mut table = [('\0', Code::Unencodable); 137];
+    let mut n = 0;
+    let mut i = 0;
+    while i < GSM7_CHARSET.len() {
+        if i != ESC as usize {
+            table[n] = (GSM7_CHARSET[i], Code::Basic(i as u8));
+            n += 1;
+        }
+        i += 1;
+    }
+    i = 0;
+    while i < GSM7_EXTENSION_CHARS.len() {
+        table[n] = (GSM7_EXTENSION_CHARS[i].0, Code::Escaped(GSM7_EXTENSION_CHARS[i].1));
+        n += 1;
+        i += 1;
+    }
+
+    // Insertion sort, which is plenty for a table this size.
+    i = 1;
+    while i < n {
+        let mut j = i;
+        while j > 0 && table[j - 1].0 as u32 > table[j].0 as u32 {
+            let t = table[j];
+            table[j] = table[j - 1];
+            table[j - 1] = t;
+            j -= 1;
+        }
+        i += 1;
+    }
+    table
+}
+
+// Encodable characters whose code satisfies `pred`, for test input generators.
+#[cfg(any(feature = "proptest", feature = "quickcheck"))]
+pub(crate) fn chars_where(pred: fn(Code) -> bool) -> Vec<char> {
+    GSM7_BY_CHAR.iter().filter(|&&(_, code)| pred(code)).map(|&(c, _)| c).collect()
+}
+
+pub(crate) fn lookup(c: char) -> Code {
+    match GSM7_BY_CHAR.binary_search_by_key(&c, |&(c, _)| c) {
+        Ok(i) => GSM7_BY_CHAR[i].1,
+        Err(_) => Code::Unencodable,
+    }
+}
+
+/// What to do when the input ends straight after an escape septet.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TruncatedEscapePolicy {
+    /// Fail with `Gsm7Error::TruncatedEscape`.
+    #[default]
+    Error,
+    /// Decode the escape as a space, the fallback 3GPP 23.038 gives for an escape a receiver
+    /// doesn't understand.
+    Space,
+}
+
+/// What to do with control characters when decoding. GSM 7-bit has three: line feed and carriage
+/// return in the basic table, and form feed in the extension table.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ControlPolicy {
+    /// Decode them as they are.
+    #[default]
+    Keep,
+    /// Decode them as the given character instead.
+    Replace(char),
+    /// Fail with `Gsm7Error::ControlChar`.
+    Reject,
+}
+
+const CONTROL_CHARS: [char; 3] = ['\n', '\r', '\x0C'];
+
+// A `ControlPolicy` and the control characters exempt from it.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct ControlFilter {
+    policy: ControlPolicy,
+    allowed: [bool; 3],
+}
+
+impl ControlFilter {
+    pub(crate) fn new(policy: ControlPolicy, allowed: &str) -> Self {
+        Self { policy, allowed: CONTROL_CHARS.map(|c| allowed.contains(c)) }
+    }
+
+    pub(crate) fn apply(&self, c: char) -> Result<char, Gsm7Error> {
+        match CONTROL_CHARS.iter().position(|&control| control == c) {
+            Some(i) if !self.allowed[i] => match self.policy {
+                ControlPolicy::Keep => Ok(c),
+                ControlPolicy::Replace(replacement) => Ok(replacement),
+                ControlPolicy::Reject => Err(Gsm7Error::ControlChar(c)),
+            },
+            _ => Ok(c),
+        }
+    }
+}
+
+// Decodes one character from a source of septets, which returns `None` at the end of the input.
+pub(crate) fn decode_next<E, F>(mut read_septet: F, policy: TruncatedEscapePolicy) -> Option<Result<char, E>>
+where
+    E: From<Gsm7Error>,
+    F: FnMut() -> Option<Result<u8, E>>
+{
+    let septet = match read_septet()? {
+        Ok(s) => s,
+        Err(e) => return Some(Err(e)),
+    };
+
+    if septet == ESC {
+        let septet = match read_septet() {
+            Some(Ok(s)) => s,
+            Some(Err(e)) => return Some(Err(e)),
+            None if policy == TruncatedEscapePolicy::Space => return Some(Ok(' ')),
+            None => return Some(Err(Gsm7Error::TruncatedEscape.into())),
+        };
+        match extension_char(septet) {
+            Some(c) => Some(Ok(c)),
+            None => Some(Err(Gsm7Error::InvalidEscape(septet).into())),
+        }
+    }
+    else {
+        Some(Ok(basic_char(septet)))
+    }
+}
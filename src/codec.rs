@@ -0,0 +1,124 @@
+use std::io;
+use std::string::String;
+
+use bytes::{Buf, BufMut, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::charset::lookup;
+use crate::pack::byte_count;
+use crate::{encode_buf, Chars, Gsm7Error};
+
+/// How `Gsm7Codec` delimits messages in a byte stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Framing {
+    /// A one-byte UDL (the length in septets) before the packed data, as in an SMS TPDU.
+    Udl8,
+    /// A big-endian two-byte UDL before the packed data.
+    Udl16,
+}
+
+impl Framing {
+    fn header_len(self) -> usize {
+        match self {
+            Framing::Udl8 => 1,
+            Framing::Udl16 => 2,
+        }
+    }
+
+    fn max_udl(self) -> usize {
+        match self {
+            Framing::Udl8 => u8::MAX as usize,
+            Framing::Udl16 => u16::MAX as usize,
+        }
+    }
+}
+
+/// `tokio_util` codec for UDL-prefixed GSM 7-bit messages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Gsm7Codec {
+    framing: Framing,
+}
+
+impl Gsm7Codec {
+    pub fn new(framing: Framing) -> Self {
+        Self { framing }
+    }
+}
+
+impl Decoder for Gsm7Codec {
+    type Item = String;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> io::Result<Option<String>> {
+        let header_len = self.framing.header_len();
+        let udl = match (self.framing, &src[..]) {
+            (Framing::Udl8, [udl, ..]) => *udl as usize,
+            (Framing::Udl16, [high, low, ..]) => u16::from_be_bytes([*high, *low]) as usize,
+            _ => return Ok(None),
+        };
+        let frame_len = header_len + byte_count(udl);
+        if src.len() < frame_len {
+            src.reserve(frame_len - src.len());
+            return Ok(None);
+        }
+        let s = Chars::with_udl(&src[header_len..frame_len], udl).collect::<Result<_, _>>();
+        src.advance(frame_len);
+        Ok(Some(s?))
+    }
+}
+
+impl<'a> Encoder<&'a str> for Gsm7Codec {
+    type Error = io::Error;
+
+    fn encode(&mut self, s: &'a str, dst: &mut BytesMut) -> io::Result<()> {
+        let udl = s
+            .chars()
+            .try_fold(0, |udl, c| Ok::<_, Gsm7Error>(udl + lookup(c).septets().ok_or(Gsm7Error::Unencodable(c))?))?;
+        if udl > self.framing.max_udl() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "message too long for framing"));
+        }
+        dst.reserve(self.framing.header_len() + byte_count(udl));
+        match self.framing {
+            Framing::Udl8 => dst.put_u8(udl as u8),
+            Framing::Udl16 => dst.put_u16(udl as u16),
+        }
+        encode_buf(s, dst)?;
+        Ok(())
+    }
+}
+
+impl Encoder<String> for Gsm7Codec {
+    type Error = io::Error;
+
+    fn encode(&mut self, s: String, dst: &mut BytesMut) -> io::Result<()> {
+        self.encode(s.as_str(), dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use tokio_util::codec::{Decoder, Encoder};
+
+    use super::{Framing, Gsm7Codec};
+
+    #[test]
+    fn codec_frames_messages() {
+        for &framing in &[Framing::Udl8, Framing::Udl16] {
+            let mut codec = Gsm7Codec::new(framing);
+            let mut buf = BytesMut::new();
+            codec.encode("{Hello}", &mut buf).unwrap();
+            codec.encode(String::from("€5"), &mut buf).unwrap();
+            let mut partial = buf.split_to(3);
+            assert_eq!(codec.decode(&mut partial).unwrap(), None);
+            partial.unsplit(buf);
+            assert_eq!(codec.decode(&mut partial).unwrap().as_deref(), Some("{Hello}"));
+            assert_eq!(codec.decode(&mut partial).unwrap().as_deref(), Some("€5"));
+            assert!(partial.is_empty());
+        }
+
+        let mut codec = Gsm7Codec::new(Framing::Udl8);
+        assert!(codec.encode("a".repeat(256).as_str(), &mut BytesMut::new()).is_err());
+        assert!(codec.encode("ú", &mut BytesMut::new()).is_err());
+    }
+}
@@ -0,0 +1,96 @@
+use crate::charset::{ESC, GSM7_CHARSET};
+use crate::pack::byte_count;
+
+/// Encodes `s` at compile time into a `[u8; N]`:
+///
+/// ```
+/// const LOW_BATTERY: [u8; 10] = gsm7::gsm7!("LOW BATTERY");
+/// ```
+#[macro_export]
+macro_rules! gsm7 {
+    ($s:expr) => {{
+        const PACKED: [u8; $crate::packed_len($s)] = $crate::encode_const($s);
+        PACKED
+    }};
+}
+
+/// Number of bytes `s` occupies when packed, assuming it uses no extension characters.
+pub const fn packed_len(s: &str) -> usize {
+    let bytes = s.as_bytes();
+    let mut septets: usize = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        // Count everything except UTF-8 continuation bytes.
+        if bytes[i] & 0xC0 != 0x80 {
+            septets += 1;
+        }
+        i += 1;
+    }
+    byte_count(septets)
+}
+
+/// Packs `s`, which must consist only of characters from the basic (non-extension) table, in the
+/// same way as `Gsm7Writer`. `N` must equal `packed_len(s)`.
+///
+/// # Panics
+///
+/// Panics if `s` contains an unencodable character or `N` is wrong. This is meant for const
+/// contexts, where the panic becomes a compile error.
+pub const fn encode_const<const N: usize>(s: &str) -> [u8; N] {
+    assert!(N == packed_len(s), "N must equal packed_len(s)");
+
+    let bytes = s.as_bytes();
+    let mut out = [0; N];
+    let mut bit = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let (c, len) = utf8_char_at(bytes, i);
+        i += len;
+        pack_septet_const(&mut out, bit, basic_septet_const(c));
+        bit += 7;
+    }
+    if bit % 8 == 7 {
+        pack_septet_const(&mut out, bit, 0x0D);
+    }
+    out
+}
+
+const fn utf8_char_at(bytes: &[u8], i: usize) -> (u32, usize) {
+    let b = bytes[i] as u32;
+    if b < 0x80 {
+        (b, 1)
+    }
+    else if b < 0xE0 {
+        (((b & 0x1F) << 6) | (bytes[i + 1] as u32 & 0x3F), 2)
+    }
+    else if b < 0xF0 {
+        (((b & 0x0F) << 12) | ((bytes[i + 1] as u32 & 0x3F) << 6) | (bytes[i + 2] as u32 & 0x3F), 3)
+    }
+    else {
+        let c = ((b & 0x07) << 18)
+            | ((bytes[i + 1] as u32 & 0x3F) << 12)
+            | ((bytes[i + 2] as u32 & 0x3F) << 6)
+            | (bytes[i + 3] as u32 & 0x3F);
+        (c, 4)
+    }
+}
+
+const fn basic_septet_const(c: u32) -> u8 {
+    let mut i = 0;
+    while i < GSM7_CHARSET.len() {
+        if i != ESC as usize && GSM7_CHARSET[i] as u32 == c {
+            return i as u8;
+        }
+        i += 1;
+    }
+    panic!("character is not in the GSM 7-bit basic character set");
+}
+
+const fn pack_septet_const<const N: usize>(out: &mut [u8; N], bit: usize, septet: u8) {
+    let byte = bit / 8;
+    let shift = bit % 8;
+    out[byte] |= septet << shift;
+    if shift > 1 && byte + 1 < N {
+        out[byte + 1] |= septet >> (8 - shift);
+    }
+}
@@ -0,0 +1,99 @@
+use std::io::{self, Write};
+use std::str;
+
+use crate::{Gsm7BufReader, Gsm7Writer};
+
+const BUF_LEN: usize = 8 * 1024;
+
+/// Decodes packed GSM 7-bit data from `reader` and writes it to `writer` as UTF-8, returning the
+/// number of characters transcoded. Memory use is bounded regardless of input size.
+pub fn copy_decode<R: io::Read, W: io::Write>(reader: R, mut writer: W) -> io::Result<u64> {
+    let mut out = [0; BUF_LEN];
+    let mut len = 0;
+    let mut count = 0;
+    for c in Gsm7BufReader::new(io::BufReader::with_capacity(BUF_LEN, reader)) {
+        let c = c?;
+        if len + c.len_utf8() > out.len() {
+            writer.write_all(&out[..len])?;
+            len = 0;
+        }
+        len += c.encode_utf8(&mut out[len..]).len();
+        count += 1;
+    }
+    writer.write_all(&out[..len])?;
+    writer.flush()?;
+    Ok(count)
+}
+
+/// Encodes UTF-8 text from `reader` and writes it to `writer` as packed GSM 7-bit data, returning
+/// the number of characters transcoded. Memory use is bounded regardless of input size.
+pub fn copy_encode<R: io::Read, W: io::Write>(mut reader: R, writer: W) -> io::Result<u64> {
+    let mut writer = Gsm7Writer::new(io::BufWriter::with_capacity(BUF_LEN, writer));
+    let mut buf = [0; BUF_LEN];
+    let mut carry = 0;
+    let mut count = 0;
+    loop {
+        let n = match reader.read(&mut buf[carry..]) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let filled = carry + n;
+
+        // A character may be split across reads; hold back its leading bytes for the next one.
+        let valid = match str::from_utf8(&buf[..filled]) {
+            Ok(_) => filled,
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => return Err(io::ErrorKind::InvalidData.into()),
+        };
+        let s = str::from_utf8(&buf[..valid]).map_err(|_| io::Error::from(io::ErrorKind::InvalidData))?;
+        for c in s.chars() {
+            writer.write_char(c)?;
+            count += 1;
+        }
+
+        buf.copy_within(valid..filled, 0);
+        carry = filled - valid;
+    }
+    if carry != 0 {
+        return Err(io::ErrorKind::InvalidData.into());
+    }
+    writer.into_writer()?.flush()?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read};
+
+    use crate::{copy_decode, copy_encode};
+
+    #[test]
+    fn round_trips_through_small_reads() -> io::Result<()> {
+        let text = "Grüße aus Köln {€} ".repeat(1000);
+
+        // One byte per read forces characters to straddle read boundaries.
+        struct Trickle<'a>(&'a [u8]);
+        impl<'a> Read for Trickle<'a> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let n = buf.len().min(self.0.len()).min(1);
+                buf[..n].copy_from_slice(&self.0[..n]);
+                self.0 = &self.0[n..];
+                Ok(n)
+            }
+        }
+
+        let mut packed = Vec::new();
+        assert_eq!(copy_encode(Trickle(text.as_bytes()), &mut packed)?, 19_000);
+
+        let mut decoded = Vec::new();
+        copy_decode(&packed[..], &mut decoded)?;
+        assert_eq!(String::from_utf8(decoded).unwrap(), text);
+
+        let mut out = Vec::new();
+        assert!(copy_encode(&b"ab\xC3"[..], &mut out).is_err());
+
+        Ok(())
+    }
+}
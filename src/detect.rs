@@ -0,0 +1,95 @@
+use crate::charset::{extension_char, ESC};
+use crate::pack::{septet_at, septet_count};
+
+/// The encoding `detect_encoding` thinks a buffer is in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Guess {
+    /// GSM 7-bit septets packed eight to seven bytes, as in a TPDU.
+    PackedGsm7,
+    /// GSM 7-bit septets one per byte, as stored by some SMSCs and modems.
+    UnpackedGsm7,
+    /// UCS-2, big-endian, as SMS sends it.
+    Ucs2,
+    /// UTF-8, which includes plain ASCII.
+    Utf8,
+    /// None of them look likely.
+    Unknown,
+}
+
+// How much of a text must be letters, digits and spaces for it to pass as text.
+const MIN_TEXTINESS: f32 = 0.7;
+
+fn is_texty(septet: u8) -> bool {
+    septet == b' ' || septet.is_ascii_alphanumeric()
+}
+
+// Whether `septets` decode with no bad escapes and look like text.
+fn is_gsm7_text(septets: impl Iterator<Item = u8>) -> bool {
+    let (mut texty, mut total, mut escaped) = (0, 0, false);
+    for septet in septets {
+        if escaped {
+            if extension_char(septet).is_none() {
+                return false;
+            }
+            escaped = false;
+        }
+        else if septet == ESC {
+            escaped = true;
+        }
+        else if is_texty(septet) {
+            texty += 1;
+        }
+        total += 1;
+    }
+    !escaped && total > 0 && texty as f32 >= total as f32 * MIN_TEXTINESS
+}
+
+// UCS-2 text mostly stays within one block, so most high bytes are the same, and the blocks that
+// SMS text uses have high bytes below 0x20, which ASCII text rarely has at every other byte.
+fn is_ucs2(bytes: &[u8]) -> bool {
+    if bytes.is_empty() || !bytes.len().is_multiple_of(2) {
+        return false;
+    }
+    let units = bytes.len() / 2;
+    let mut counts = [0usize; 0x20];
+    for &high in bytes.iter().step_by(2).filter(|&&high| high < 0x20) {
+        counts[high as usize] += 1;
+    }
+    let common = counts.iter().max().copied().unwrap_or(0);
+    let units_be = bytes.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]]));
+    common as f32 >= units as f32 * 0.6 && char::decode_utf16(units_be).all(|c| c.is_ok())
+}
+
+/// Guesses the encoding of a message body with no DCS to say what it is, from what each
+/// encoding would decode it to.
+///
+/// UCS-2 is recognised by its high bytes, so text from several blocks at once, or CJK text,
+/// may not be. Text that is all ASCII letters, digits, punctuation and line breaks reads the same
+/// as UTF-8 or unpacked GSM 7-bit, and is reported as UTF-8; other control bytes mean unpacked
+/// GSM 7-bit, in which they are letters such as `è` and `Δ`. Anything else is taken to be packed
+/// GSM 7-bit if at least 70% of it decodes to letters, digits and spaces.
+pub fn detect_encoding(bytes: &[u8]) -> Guess {
+    if bytes.is_empty() {
+        return Guess::Unknown;
+    }
+    if is_ucs2(bytes) {
+        return Guess::Ucs2;
+    }
+    if bytes.iter().all(|&b| b < 0x80) {
+        if bytes.iter().all(|&b| b >= 0x20 || b == b'\n' || b == b'\r' || b == b'\t') {
+            return Guess::Utf8;
+        }
+        if is_gsm7_text(bytes.iter().copied()) {
+            return Guess::UnpackedGsm7;
+        }
+    }
+    else if core::str::from_utf8(bytes).is_ok() {
+        return Guess::Utf8;
+    }
+    let packed = (0..septet_count(bytes.len())).filter_map(|i| septet_at(bytes, i));
+    if is_gsm7_text(packed) {
+        return Guess::PackedGsm7;
+    }
+    Guess::Unknown
+}
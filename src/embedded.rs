@@ -0,0 +1,424 @@
+//! Reader and writer over the `embedded-io` traits, for use with embedded HAL drivers in `no_std`
+//! environments. Neither allocates. The async variants over `embedded-io-async` need the
+//! `embedded-io-async` feature.
+
+use core::fmt;
+
+use embedded_io::{ErrorKind, Read, Write};
+#[cfg(feature = "embedded-io-async")]
+use embedded_io_async::{Read as AsyncRead, Write as AsyncWrite};
+
+use crate::charset::{decode_next, lookup, Code, TruncatedEscapePolicy, ESC};
+use crate::pack::{byte_count, Packer};
+use crate::Gsm7Error;
+
+const BUF_LEN: usize = 32;
+
+/// Error from the underlying reader or writer, or from decoding or encoding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Error<E> {
+    Io(E),
+    Gsm7(Gsm7Error),
+}
+
+impl<E> From<Gsm7Error> for Error<E> {
+    fn from(e: Gsm7Error) -> Self {
+        Error::Gsm7(e)
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => e.fmt(f),
+            Error::Gsm7(e) => e.fmt(f),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> core::error::Error for Error<E> {}
+
+impl<E: embedded_io::Error> embedded_io::Error for Error<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Error::Io(e) => e.kind(),
+            Error::Gsm7(Gsm7Error::BufferTooSmall) => ErrorKind::WriteZero,
+            Error::Gsm7(_) => ErrorKind::InvalidData,
+        }
+    }
+}
+
+// Bytes read but not yet unpacked, and the bits of a partially unpacked septet.
+#[derive(Debug, Default)]
+struct ReadState {
+    buf: [u8; BUF_LEN],
+    pos: usize,
+    len: usize,
+    acc: u16,
+    bits: u8,
+    // With a UDL, the septets left to decode and the bytes left to read.
+    limit: Option<(usize, usize)>,
+    eof: bool,
+}
+
+impl ReadState {
+    fn with_udl(udl: usize) -> Self {
+        Self { limit: Some((udl, byte_count(udl))), ..Self::default() }
+    }
+
+    // Whether more input is needed to hold the two septets of an escape sequence.
+    fn needs_input(&self) -> bool {
+        !self.eof && (self.len - self.pos) * 8 + (self.bits as usize) < 14
+    }
+
+    // Space for the next read, never extending past the user data.
+    fn spare(&mut self) -> &mut [u8] {
+        self.buf.copy_within(self.pos..self.len, 0);
+        self.len -= self.pos;
+        self.pos = 0;
+        let max = BUF_LEN - self.len;
+        let max = self.limit.map_or(max, |(_, bytes)| bytes.min(max));
+        let spare = &mut self.buf[self.len..self.len + max];
+        if spare.is_empty() {
+            self.eof = true;
+        }
+        spare
+    }
+
+    fn filled(&mut self, len: usize) {
+        if len == 0 {
+            self.eof = true;
+        }
+        if let Some((_, bytes)) = &mut self.limit {
+            *bytes -= len;
+        }
+        self.len += len;
+    }
+
+    fn read_septet(&mut self) -> Option<Result<u8, Gsm7Error>> {
+        if let Some((remaining, _)) = &mut self.limit {
+            if *remaining == 0 {
+                return None;
+            }
+            *remaining -= 1;
+        }
+        if self.bits < 7 {
+            match self.buf[self.pos..self.len].first() {
+                Some(&byte) => {
+                    self.acc |= (byte as u16) << self.bits;
+                    self.bits += 8;
+                    self.pos += 1;
+                },
+                None if self.limit.is_some() => return Some(Err(Gsm7Error::UnexpectedEnd)),
+                None => return None,
+            }
+        }
+        let septet = self.acc as u8 & 0x7F;
+        self.acc >>= 7;
+        self.bits -= 7;
+        Some(Ok(septet))
+    }
+
+    fn decode<E>(&mut self) -> Option<Result<char, Error<E>>> {
+        decode_next(|| self.read_septet().map(|r| r.map_err(Error::Gsm7)), TruncatedEscapePolicy::Error)
+    }
+}
+
+// Complete bytes not yet written, and the packer holding any partial byte.
+#[derive(Debug, Default)]
+struct WriteState {
+    buf: [u8; BUF_LEN],
+    len: usize,
+    packer: Packer,
+}
+
+impl WriteState {
+    fn has_room(&self) -> bool {
+        // An escape sequence completes at most two bytes.
+        self.len + 2 <= BUF_LEN
+    }
+
+    fn push_char(&mut self, c: char) -> Result<(), Gsm7Error> {
+        match lookup(c) {
+            Code::Basic(septet) => self.push(septet),
+            Code::Escaped(septet) => {
+                self.push(ESC);
+                self.push(septet);
+            },
+            Code::Unencodable => return Err(Gsm7Error::Unencodable(c)),
+        }
+        Ok(())
+    }
+
+    fn push(&mut self, septet: u8) {
+        if let Some(byte) = self.packer.push(septet) {
+            self.buf[self.len] = byte;
+            self.len += 1;
+        }
+    }
+
+    fn finish(&mut self) {
+        if let Some(byte) = core::mem::take(&mut self.packer).finish() {
+            self.buf[self.len] = byte;
+            self.len += 1;
+        }
+    }
+}
+
+/// Decoder for packed GSM 7-bit data from an `embedded_io::Read`.
+pub struct Gsm7Reader<R> {
+    reader: R,
+    state: ReadState,
+}
+
+impl<R: Read> Gsm7Reader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, state: ReadState::default() }
+    }
+
+    /// Creates a reader that decodes exactly `udl` septets, never reading past the bytes that
+    /// hold them.
+    pub fn with_udl(reader: R, udl: usize) -> Self {
+        Self { reader, state: ReadState::with_udl(udl) }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: Read> Iterator for Gsm7Reader<R> {
+    type Item = Result<char, Error<R::Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.state.needs_input() {
+            let spare = self.state.spare();
+            let len = if spare.is_empty() {
+                0
+            }
+            else {
+                match self.reader.read(spare) {
+                    Ok(len) => len,
+                    Err(e) => return Some(Err(Error::Io(e))),
+                }
+            };
+            self.state.filled(len);
+        }
+        self.state.decode()
+    }
+}
+
+/// Encoder writing packed GSM 7-bit data to an `embedded_io::Write`.
+pub struct Gsm7Writer<W> {
+    writer: W,
+    state: WriteState,
+}
+
+impl<W: Write> Gsm7Writer<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer, state: WriteState::default() }
+    }
+
+    pub fn write_char(&mut self, c: char) -> Result<(), Error<W::Error>> {
+        if !self.state.has_room() {
+            self.write_buf()?;
+        }
+        Ok(self.state.push_char(c)?)
+    }
+
+    pub fn write_str(&mut self, s: &str) -> Result<(), Error<W::Error>> {
+        s.chars().try_for_each(|c| self.write_char(c))
+    }
+
+    /// Writes out all complete bytes and flushes the underlying writer.
+    pub fn flush(&mut self) -> Result<(), Error<W::Error>> {
+        self.write_buf()?;
+        self.writer.flush().map_err(Error::Io)
+    }
+
+    /// Writes the final partial byte, padded as `Gsm7Writer::into_writer` pads it, flushes, and
+    /// returns the underlying writer.
+    pub fn into_writer(mut self) -> Result<W, Error<W::Error>> {
+        if !self.state.has_room() {
+            self.write_buf()?;
+        }
+        self.state.finish();
+        self.flush()?;
+        Ok(self.writer)
+    }
+
+    // Unlike `Write::write_all`, treats a writer that accepts nothing as an error, not a panic.
+    fn write_buf(&mut self) -> Result<(), Error<W::Error>> {
+        let mut pos = 0;
+        while pos < self.state.len {
+            match self.writer.write(&self.state.buf[pos..self.state.len]).map_err(Error::Io)? {
+                0 => return Err(Gsm7Error::BufferTooSmall.into()),
+                len => pos += len,
+            }
+        }
+        self.state.len = 0;
+        Ok(())
+    }
+}
+
+/// Lets `uwrite!` and `uwriteln!` format straight into the packed stream.
+#[cfg(feature = "ufmt")]
+impl<W: Write> ufmt::uWrite for Gsm7Writer<W> {
+    type Error = Error<W::Error>;
+
+    fn write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        Gsm7Writer::write_str(self, s)
+    }
+
+    fn write_char(&mut self, c: char) -> Result<(), Self::Error> {
+        Gsm7Writer::write_char(self, c)
+    }
+}
+
+/// Asynchronous decoder for packed GSM 7-bit data from an `embedded_io_async::Read`.
+#[cfg(feature = "embedded-io-async")]
+pub struct AsyncGsm7Reader<R> {
+    reader: R,
+    state: ReadState,
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<R: AsyncRead> AsyncGsm7Reader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, state: ReadState::default() }
+    }
+
+    /// Creates a reader that decodes exactly `udl` septets, never reading past the bytes that
+    /// hold them.
+    pub fn with_udl(reader: R, udl: usize) -> Self {
+        Self { reader, state: ReadState::with_udl(udl) }
+    }
+
+    /// Reads the next character, or `None` at the end of the input.
+    pub async fn read_char(&mut self) -> Result<Option<char>, Error<R::Error>> {
+        while self.state.needs_input() {
+            let spare = self.state.spare();
+            let len = if spare.is_empty() { 0 } else { self.reader.read(spare).await.map_err(Error::Io)? };
+            self.state.filled(len);
+        }
+        self.state.decode().transpose()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// Asynchronous encoder writing packed GSM 7-bit data to an `embedded_io_async::Write`.
+#[cfg(feature = "embedded-io-async")]
+pub struct AsyncGsm7Writer<W> {
+    writer: W,
+    state: WriteState,
+}
+
+#[cfg(feature = "embedded-io-async")]
+impl<W: AsyncWrite> AsyncGsm7Writer<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer, state: WriteState::default() }
+    }
+
+    pub async fn write_char(&mut self, c: char) -> Result<(), Error<W::Error>> {
+        if !self.state.has_room() {
+            self.write_buf().await?;
+        }
+        Ok(self.state.push_char(c)?)
+    }
+
+    pub async fn write_str(&mut self, s: &str) -> Result<(), Error<W::Error>> {
+        for c in s.chars() {
+            self.write_char(c).await?;
+        }
+        Ok(())
+    }
+
+    /// Writes out all complete bytes and flushes the underlying writer.
+    pub async fn flush(&mut self) -> Result<(), Error<W::Error>> {
+        self.write_buf().await?;
+        self.writer.flush().await.map_err(Error::Io)
+    }
+
+    /// Writes the final partial byte, padded as `Gsm7Writer::into_writer` pads it, flushes, and
+    /// returns the underlying writer.
+    pub async fn into_writer(mut self) -> Result<W, Error<W::Error>> {
+        if !self.state.has_room() {
+            self.write_buf().await?;
+        }
+        self.state.finish();
+        self.flush().await?;
+        Ok(self.writer)
+    }
+
+    async fn write_buf(&mut self) -> Result<(), Error<W::Error>> {
+        let mut pos = 0;
+        while pos < self.state.len {
+            match self.writer.write(&self.state.buf[pos..self.state.len]).await.map_err(Error::Io)? {
+                0 => return Err(Gsm7Error::BufferTooSmall.into()),
+                len => pos += len,
+            }
+        }
+        self.state.len = 0;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::{Error, Gsm7Reader, Gsm7Writer};
+    use crate::Gsm7Error;
+
+    #[test]
+    fn embedded_round_trip() {
+        let text = "{Hello} €5 ".repeat(20);
+        let mut buf = [0; 512];
+        let mut writer = Gsm7Writer::new(&mut buf[..]);
+        writer.write_str(&text).unwrap();
+        let len = 512 - writer.into_writer().unwrap().len();
+        let packed = buf[..len].to_vec();
+        assert_eq!(packed, crate::encode(&text).unwrap());
+
+        let s = Gsm7Reader::new(&packed[..]).collect::<Result<String, _>>().unwrap();
+        assert_eq!(s.trim_end_matches('\r'), text);
+
+        let mut reader = Gsm7Reader::with_udl(&packed[..], 7);
+        assert_eq!(reader.by_ref().collect::<Result<String, _>>().unwrap(), "{Hello");
+        assert_eq!(reader.into_inner(), &packed[7..]);
+        let mut reader = Gsm7Reader::with_udl(&packed[..2], 7);
+        assert_eq!(reader.nth(2), Some(Err(Error::Gsm7(Gsm7Error::UnexpectedEnd))));
+
+        #[cfg(feature = "ufmt")]
+        {
+            let mut buf = [0; 16];
+            let mut writer = Gsm7Writer::new(&mut buf[..]);
+            ufmt::uwrite!(writer, "{}% {}", 42u8, "€").unwrap();
+            let len = 16 - writer.into_writer().unwrap().len();
+            assert_eq!(&buf[..len], crate::encode("42% €").unwrap());
+        }
+
+        let mut buf = [0; 4];
+        let mut writer = Gsm7Writer::new(&mut buf[..]);
+        writer.write_str("Hello").unwrap();
+        assert!(matches!(writer.into_writer(), Err(Error::Io(_))));
+
+        #[cfg(feature = "embedded-io-async")]
+        futures::executor::block_on(async {
+            let mut buf = [0; 512];
+            let mut writer = super::AsyncGsm7Writer::new(&mut buf[..]);
+            writer.write_str(&text).await.unwrap();
+            assert_eq!(512 - writer.into_writer().await.unwrap().len(), len);
+            assert_eq!(&buf[..len], packed);
+
+            let mut reader = super::AsyncGsm7Reader::new(&packed[..]);
+            let mut s = String::new();
+            while let Some(c) = reader.read_char().await.unwrap() {
+                s.push(c);
+            }
+            assert_eq!(s.trim_end_matches('\r'), text);
+        });
+    }
+}
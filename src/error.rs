@@ -0,0 +1,110 @@
+use core::fmt;
+
+#[cfg(feature = "std")]
+use std::io;
+
+/// Errors from encoding or decoding.
+///
+/// APIs built on `std::io` report these as an `io::Error` carrying the `Gsm7Error` as its source;
+/// see the `From` impl for the kinds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Gsm7Error {
+    /// An escape was followed by a septet with no meaning in the extension table.
+    InvalidEscape(u8),
+    /// The input ended in the middle of a character, or before the expected number of septets.
+    UnexpectedEnd,
+    /// The character has no GSM 7-bit encoding.
+    Unencodable(char),
+    /// The output buffer is too small.
+    BufferTooSmall,
+    /// The data coding scheme does not specify uncompressed GSM 7-bit text.
+    UnsupportedDcs(u8),
+    /// The input is not an even number of hex digits.
+    InvalidHex,
+    /// The input ended straight after an escape septet.
+    TruncatedEscape,
+    /// The character would take the text past the septet budget it was given.
+    WouldOverflow,
+    /// A control character was decoded where `ControlPolicy::Reject` forbids one.
+    ControlChar(char),
+}
+
+impl fmt::Display for Gsm7Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Gsm7Error::InvalidEscape(septet) => write!(f, "invalid extension septet 0x{:02X}", septet),
+            Gsm7Error::UnexpectedEnd => f.write_str("unexpected end of input"),
+            Gsm7Error::Unencodable(c) => write!(f, "character {:?} has no GSM 7-bit encoding", c),
+            Gsm7Error::BufferTooSmall => f.write_str("output buffer too small"),
+            Gsm7Error::UnsupportedDcs(dcs) => write!(f, "data coding scheme 0x{:02X} is not GSM 7-bit", dcs),
+            Gsm7Error::InvalidHex => f.write_str("invalid hex"),
+            Gsm7Error::TruncatedEscape => f.write_str("input ends with an escape"),
+            Gsm7Error::WouldOverflow => f.write_str("septet budget exhausted"),
+            Gsm7Error::ControlChar(c) => write!(f, "control character {:?}", c),
+        }
+    }
+}
+
+impl core::error::Error for Gsm7Error {}
+
+/// A decoding error from one of the readers, with where it happened. The reader's `io::Error`
+/// carries this as its source, with the same kind `Gsm7Error` alone would map to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeError {
+    pub error: Gsm7Error,
+    /// The septet offset, from where the reader started, of the character that failed.
+    pub septet: usize,
+}
+
+impl DecodeError {
+    /// The bit offset of the character that failed, from where the reader started. Bits are
+    /// numbered from the least significant of each byte, so the character starts in byte
+    /// `bit() / 8`.
+    pub fn bit(&self) -> usize {
+        self.septet * 7
+    }
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at septet {} (bit {})", self.error, self.septet, self.bit())
+    }
+}
+
+impl core::error::Error for DecodeError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+// Reports a decoding failure at the given septet offset, when the `tracing` feature is enabled.
+#[inline]
+pub(crate) fn trace_decode_error<E: fmt::Display>(_error: &E, _septet: usize) {
+    #[cfg(feature = "tracing")]
+    tracing::debug!(septet = _septet, error = %_error, "GSM 7-bit decoding failed");
+}
+
+#[cfg(feature = "std")]
+impl Gsm7Error {
+    // The kind of `io::Error` this is reported as.
+    pub(crate) fn io_kind(&self) -> io::ErrorKind {
+        match self {
+            Gsm7Error::UnexpectedEnd => io::ErrorKind::UnexpectedEof,
+            Gsm7Error::BufferTooSmall | Gsm7Error::WouldOverflow => io::ErrorKind::WriteZero,
+            Gsm7Error::InvalidEscape(_) | Gsm7Error::Unencodable(_) | Gsm7Error::UnsupportedDcs(_)
+            | Gsm7Error::InvalidHex
+            | Gsm7Error::TruncatedEscape
+            | Gsm7Error::ControlChar(_) => io::ErrorKind::InvalidData,
+        }
+    }
+}
+
+/// Keeps the `Gsm7Error` as the source, so that `get_ref` and `downcast` can recover it.
+#[cfg(feature = "std")]
+impl From<Gsm7Error> for io::Error {
+    fn from(e: Gsm7Error) -> Self {
+        io::Error::new(e.io_kind(), e)
+    }
+}
@@ -0,0 +1,99 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::charset::{basic_char, extension_char, Table, ESC};
+use crate::pack::{septet_at, septet_count};
+
+const CR: u8 = 0x0D;
+
+/// Something wrong, or possibly wrong, with a decoded character.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Issue {
+    /// The escape is followed by a septet with no meaning in the extension table. The character is
+    /// the basic table's reading of that septet, which is what 3GPP 23.038 says to display.
+    InvalidEscape,
+    /// The input ends straight after an escape.
+    TruncatedEscape,
+    /// The input ends before the UDL says it should. The annotation covers the missing bits.
+    UnexpectedEnd,
+    /// The character fills the last seven bits of input with no UDL to say whether it is text, so
+    /// it may be CR padding (a carriage return) or zero fill (an `@`).
+    PossiblePadding,
+}
+
+/// An issue found by `decode_with_report`, at the septet offset where the character starts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DecodeIssue {
+    pub septet: usize,
+    pub issue: Issue,
+}
+
+/// One decoded character of packed data, and where it came from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Annotation {
+    /// The range of bits in the input holding the character's septets.
+    pub bits: Range<usize>,
+    /// The septets, including the escape for extension characters.
+    pub septets: Vec<u8>,
+    pub table: Table,
+    /// The character, if the septets make one.
+    pub char: Option<char>,
+    pub issue: Option<Issue>,
+}
+
+impl Annotation {
+    fn new(septets: Range<usize>, values: &[u8], table: Table, c: Option<char>, issue: Option<Issue>) -> Self {
+        let bits = septets.start * 7..septets.end * 7;
+        Self { bits, septets: values.to_vec(), table, char: c, issue }
+    }
+}
+
+/// Decodes `bytes`, or the first `udl` septets of them, annotating each character with its bit
+/// range, septets and table, and carrying on past anything that would make `decode` fail.
+pub fn explain_bytes(bytes: &[u8], udl: Option<usize>) -> Vec<Annotation> {
+    let available = septet_count(bytes.len());
+    let end = udl.unwrap_or(available).min(available);
+    let mut annotations = Vec::with_capacity(end);
+    let mut i = 0;
+    while let Some(septet) = septet_at(bytes, i).filter(|_| i < end) {
+        let annotation = if septet != ESC {
+            let last = udl.is_none() && i + 1 == end && available.is_multiple_of(8);
+            let issue = if last && (septet == CR || septet == 0) { Some(Issue::PossiblePadding) } else { None };
+            Annotation::new(i..i + 1, &[septet], Table::Basic, Some(basic_char(septet)), issue)
+        }
+        else if let Some(next) = septet_at(bytes, i + 1).filter(|_| i + 1 < end) {
+            let (c, issue) = match extension_char(next) {
+                Some(c) => (c, None),
+                None => (basic_char(next), Some(Issue::InvalidEscape)),
+            };
+            i += 1;
+            Annotation::new(i - 1..i + 1, &[ESC, next], Table::Extension, Some(c), issue)
+        }
+        else {
+            Annotation::new(i..i + 1, &[ESC], Table::Extension, None, Some(Issue::TruncatedEscape))
+        };
+        annotations.push(annotation);
+        i += 1;
+    }
+    if let Some(udl) = udl.filter(|&udl| udl > i) {
+        annotations.push(Annotation::new(i..udl, &[], Table::Basic, None, Some(Issue::UnexpectedEnd)));
+    }
+    annotations
+}
+
+/// Decodes as much of `bytes` as possible, reporting what `decode` would have failed on rather
+/// than losing the text. Invalid escapes decode as the basic table's reading of the septet after
+/// the escape, and a truncated escape is dropped.
+pub fn decode_with_report(bytes: &[u8]) -> (String, Vec<DecodeIssue>) {
+    let mut text = String::with_capacity(bytes.len());
+    let mut issues = Vec::new();
+    for annotation in explain_bytes(bytes, None) {
+        text.extend(annotation.char);
+        if let Some(issue) = annotation.issue {
+            issues.push(DecodeIssue { septet: annotation.bits.start / 7, issue });
+        }
+    }
+    (text, issues)
+}
@@ -0,0 +1,155 @@
+//! C interface. Functions take explicit buffers, never allocate, and return a `Gsm7Status`.
+//! `cbindgen` generates a header from this module using the `cbindgen.toml` in the crate root, and
+//! `cargo rustc --release --features ffi --crate-type staticlib` builds a library to link against.
+
+use core::{ptr, slice, str};
+
+use crate::{segment_count, Chars, Gsm7Error};
+
+/// Passed as `udl` to `gsm7_decode` to decode every septet in the input.
+pub const GSM7_NO_UDL: usize = !0;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Gsm7Status {
+    Ok = 0,
+    NullPointer = -1,
+    InvalidUtf8 = -2,
+    Unencodable = -3,
+    InvalidEscape = -4,
+    UnexpectedEnd = -5,
+    BufferTooSmall = -6,
+    UnsupportedDcs = -7,
+    InvalidHex = -8,
+    TruncatedEscape = -9,
+    WouldOverflow = -10,
+    ControlChar = -11,
+}
+
+impl From<Gsm7Error> for Gsm7Status {
+    fn from(e: Gsm7Error) -> Self {
+        match e {
+            Gsm7Error::InvalidEscape(_) => Gsm7Status::InvalidEscape,
+            Gsm7Error::UnexpectedEnd => Gsm7Status::UnexpectedEnd,
+            Gsm7Error::Unencodable(_) => Gsm7Status::Unencodable,
+            Gsm7Error::BufferTooSmall => Gsm7Status::BufferTooSmall,
+            Gsm7Error::UnsupportedDcs(_) => Gsm7Status::UnsupportedDcs,
+            Gsm7Error::InvalidHex => Gsm7Status::InvalidHex,
+            Gsm7Error::TruncatedEscape => Gsm7Status::TruncatedEscape,
+            Gsm7Error::WouldOverflow => Gsm7Status::WouldOverflow,
+            Gsm7Error::ControlChar(_) => Gsm7Status::ControlChar,
+        }
+    }
+}
+
+unsafe fn input<'a>(data: *const u8, len: usize) -> Result<&'a [u8], Gsm7Status> {
+    if len == 0 {
+        Ok(&[])
+    }
+    else if data.is_null() {
+        Err(Gsm7Status::NullPointer)
+    }
+    else {
+        Ok(slice::from_raw_parts(data, len))
+    }
+}
+
+unsafe fn output<'a>(data: *mut u8, cap: usize) -> Result<&'a mut [u8], Gsm7Status> {
+    if cap == 0 {
+        Ok(&mut [])
+    }
+    else if data.is_null() {
+        Err(Gsm7Status::NullPointer)
+    }
+    else {
+        Ok(slice::from_raw_parts_mut(data, cap))
+    }
+}
+
+unsafe fn text<'a>(data: *const u8, len: usize) -> Result<&'a str, Gsm7Status> {
+    str::from_utf8(input(data, len)?).map_err(|_| Gsm7Status::InvalidUtf8)
+}
+
+fn status(result: Result<usize, Gsm7Status>, out_len: *mut usize) -> Gsm7Status {
+    match result {
+        Ok(_) if out_len.is_null() => Gsm7Status::NullPointer,
+        Ok(len) => {
+            // Safety: checked for null above; validity is the caller's obligation.
+            unsafe { ptr::write(out_len, len) };
+            Gsm7Status::Ok
+        },
+        Err(status) => status,
+    }
+}
+
+/// Encodes `text_len` bytes of UTF-8 at `text` into the `out_cap` bytes at `out`, storing the
+/// number of bytes written in `*out_len`.
+///
+/// # Safety
+///
+/// `text` must point to `text_len` readable bytes, `out` to `out_cap` writable bytes, and
+/// `out_len` to a writable `size_t`.
+#[no_mangle]
+pub unsafe extern "C" fn gsm7_encode(
+    text: *const u8,
+    text_len: usize,
+    out: *mut u8,
+    out_cap: usize,
+    out_len: *mut usize,
+) -> Gsm7Status {
+    let result = (|| -> Result<usize, Gsm7Status> {
+        let text = self::text(text, text_len)?;
+        let out = output(out, out_cap)?;
+        Ok(crate::encode_into(text, out)?)
+    })();
+    status(result, out_len)
+}
+
+/// Decodes the `packed_len` bytes at `packed` into UTF-8 in the `out_cap` bytes at `out`, storing
+/// the number of bytes written in `*out_len`. Decodes exactly `udl` septets, or everything if
+/// `udl` is `GSM7_NO_UDL`. The output is not NUL-terminated.
+///
+/// # Safety
+///
+/// `packed` must point to `packed_len` readable bytes, `out` to `out_cap` writable bytes, and
+/// `out_len` to a writable `size_t`.
+#[no_mangle]
+pub unsafe extern "C" fn gsm7_decode(
+    packed: *const u8,
+    packed_len: usize,
+    udl: usize,
+    out: *mut u8,
+    out_cap: usize,
+    out_len: *mut usize,
+) -> Gsm7Status {
+    let result = (|| -> Result<usize, Gsm7Status> {
+        let packed = input(packed, packed_len)?;
+        let out = output(out, out_cap)?;
+        let chars = if udl == GSM7_NO_UDL { Chars::new(packed) } else { Chars::with_udl(packed, udl) };
+        let mut len = 0;
+        for c in chars {
+            let c = c?;
+            let buf = out.get_mut(len..len + c.len_utf8()).ok_or(Gsm7Error::BufferTooSmall)?;
+            len += c.encode_utf8(buf).len();
+        }
+        Ok(len)
+    })();
+    status(result, out_len)
+}
+
+/// Stores in `*out_count` the number of SMS segments needed to send `text_len` bytes of UTF-8 at
+/// `text`.
+///
+/// # Safety
+///
+/// `text` must point to `text_len` readable bytes and `out_count` to a writable `size_t`.
+#[no_mangle]
+pub unsafe extern "C" fn gsm7_segment_count(
+    text: *const u8,
+    text_len: usize,
+    out_count: *mut usize,
+) -> Gsm7Status {
+    let result = (|| -> Result<usize, Gsm7Status> { Ok(segment_count(self::text(text, text_len)?)?) })();
+    status(result, out_count)
+}
+
@@ -0,0 +1,136 @@
+use alloc::{string::String, vec::Vec};
+#[cfg(feature = "std")]
+use std::io;
+
+use crate::{decode, encode, Gsm7Error};
+
+const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+/// Encodes `s` as packed septets written as uppercase hex digits, as modems and SMPP tooling
+/// exchange them.
+pub fn encode_to_hex(s: &str) -> Result<String, Gsm7Error> {
+    encode(s).map(|packed| to_hex(&packed))
+}
+
+/// Decodes packed septets written as hex digits in either case, ignoring whitespace.
+pub fn decode_from_hex(s: &str) -> Result<String, Gsm7Error> {
+    decode(&from_hex(s).ok_or(Gsm7Error::InvalidHex)?)
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len() * 2);
+    for &b in bytes {
+        s.push(DIGITS[(b >> 4) as usize] as char);
+        s.push(DIGITS[(b & 0xF) as usize] as char);
+    }
+    s
+}
+
+// Parses pairs of hex digits, skipping whitespace between and within them.
+pub(crate) fn from_hex(s: &str) -> Option<Vec<u8>> {
+    let mut digits = s.bytes().filter(|b| !b.is_ascii_whitespace());
+    let mut out = Vec::with_capacity(s.len() / 2);
+    while let Some(high) = digits.next() {
+        out.push(nibble(high)? << 4 | nibble(digits.next()?)?);
+    }
+    Some(out)
+}
+
+fn nibble(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Reads bytes from a source of ASCII hex digit pairs, such as a modem log of hex lines, skipping
+/// whitespace. Wrap it in a `Gsm7Reader` to decode the packed data without buffering it.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct HexReader<R> {
+    reader: R,
+}
+
+#[cfg(feature = "std")]
+impl<R: io::BufRead> HexReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn digit(&mut self) -> io::Result<Option<u8>> {
+        loop {
+            let c = match self.reader.fill_buf()?.first() {
+                Some(&c) => c,
+                None => return Ok(None),
+            };
+            self.reader.consume(1);
+            if !c.is_ascii_whitespace() {
+                return nibble(c).map(Some).ok_or_else(|| Gsm7Error::InvalidHex.into());
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: io::BufRead> io::Read for HexReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut len = 0;
+        while len < buf.len() {
+            let high = match self.digit()? {
+                Some(high) => high,
+                None => break,
+            };
+            let low = self.digit()?.ok_or(Gsm7Error::InvalidHex)?;
+            buf[len] = high << 4 | low;
+            len += 1;
+        }
+        Ok(len)
+    }
+}
+
+/// Writes each byte as two uppercase hex digits. Wrap it in a `Gsm7Writer` to produce hex
+/// directly.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct HexWriter<W> {
+    writer: W,
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> HexWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: io::Write> io::Write for HexWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut digits = [0; 64];
+        let n = buf.len().min(digits.len() / 2);
+        for (pair, &b) in digits.chunks_mut(2).zip(&buf[..n]) {
+            pair[0] = DIGITS[(b >> 4) as usize];
+            pair[1] = DIGITS[(b & 0xF) as usize];
+        }
+        self.writer.write_all(&digits[..n * 2])?;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
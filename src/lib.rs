@@ -1,197 +1,1959 @@
-use std::io;
+//! GSM-7 (aka GSM 03.38 or 3GPP 23.038) encoding and decoding.
+//!
+//! The crate is `no_std` when the default `std` feature is disabled. `Chars`, `Decoder`,
+//! `Encoder`, `Gsm7ArrayString`, `decode_into` and `encode_into` work without an allocator, as do
+//! `decode_heapless` and
+//! `encode_heapless` with the `heapless` feature, and the `embedded` reader and writer with the
+//! `embedded-io` feature; the `alloc` feature adds `decode` and `encode`; `std` adds the
+//! `io`-based reader and writer.
+//!
+//! Nothing in this crate panics on malformed input or unencodable text; such input always results
+//! in an error. The only exception is `encode_const`, whose panics are meant to happen at compile
+//! time.
 
-use bitstream_io::{BitReader, BitWriter, LittleEndian, Numeric};
+#![cfg_attr(not(feature = "std"), no_std)]
 
-type Endianness = LittleEndian;
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
-const ESC: u8 = 0x1B;
+mod alphabet;
+mod array_string;
+mod charset;
+#[cfg(feature = "tokio-util")]
+mod codec;
+mod const_encode;
+#[cfg(feature = "std")]
+mod copy;
+mod detect;
+#[cfg(feature = "embedded-io")]
+pub mod embedded;
+mod error;
+#[cfg(feature = "alloc")]
+mod explain;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "alloc")]
+mod hex;
+mod pack;
+#[cfg(feature = "alloc")]
+pub mod pdu;
+#[cfg(feature = "alloc")]
+mod plan;
+mod push;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "std")]
+mod reader;
+mod segment;
+#[cfg(feature = "serde")]
+pub mod serde_packed;
+mod slice;
+#[cfg(feature = "proptest")]
+pub mod strategy;
+#[cfg(feature = "futures")]
+mod stream;
+#[cfg(feature = "alloc")]
+mod string;
+#[cfg(feature = "tokio")]
+mod tokio_io;
+#[cfg(feature = "alloc")]
+mod transliterate;
+#[cfg(feature = "alloc")]
+pub mod urc;
+#[cfg(feature = "alloc")]
+mod user_data;
+#[cfg(feature = "vectors")]
+pub mod vectors;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "std")]
+mod writer;
 
-static GSM7_CHARSET: [char; 128] = [
-    '@', '£', '$', '¥', 'è', 'é', 'ù', 'ì',  'ò', 'Ç', '\n', 'Ø',    'ø', '\r', 'Å', 'å',
-    'Δ', '_', 'Φ', 'Γ', 'Λ', 'Ω', 'Π', 'Ψ',  'Σ', 'Θ', 'Ξ',  '\x1B', 'Æ', 'æ',  'ß', 'É',
-    ' ', '!', '"', '#', '¤', '%', '&', '\'', '(', ')', '*',  '+',    ',', '-',  '.', '/',
-    '0', '1', '2', '3', '4', '5', '6', '7', '8',  '9', ':',  ';',    '<', '=',  '>', '?',
-    '¡', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H',  'I', 'J',  'K',    'L', 'M',  'N', 'O',
-    'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X',  'Y', 'Z',  'Ä',    'Ö', 'Ñ',  'Ü', '§',
-    '¿', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h',  'i', 'j',  'k',    'l', 'm',  'n', 'o',
-    'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x',  'y', 'z',  'ä',    'ö', 'ñ',  'ü', 'à',
-];
+pub use alphabet::{Alphabet, NationalLanguage};
+#[cfg(feature = "alloc")]
+pub use alphabet::{coverage, Coverage};
+pub use array_string::Gsm7ArrayString;
+#[cfg(feature = "tokio-util")]
+pub use codec::{Framing, Gsm7Codec};
+pub use charset::{
+    first_non_gsm7_char, is_basic_char, is_extension_char, is_gsm7_char, requires_ucs2, supported_chars, ControlPolicy,
+    NonGsm7Char, SupportedChar, Table, TruncatedEscapePolicy,
+    EXTENSION_CHARS, GSM7_CHARSET, GSM7_EXTENSION, GSM7_EXTENSION_CHARS,
+};
+pub use const_encode::{encode_const, packed_len};
+#[cfg(feature = "std")]
+pub use copy::{copy_decode, copy_encode};
+pub use detect::{detect_encoding, Guess};
+pub use error::{DecodeError, Gsm7Error};
+#[cfg(feature = "alloc")]
+pub use explain::{decode_with_report, explain_bytes, Annotation, DecodeIssue, Issue};
+#[cfg(feature = "alloc")]
+pub use hex::{decode_from_hex, encode_to_hex};
+#[cfg(feature = "std")]
+pub use hex::{HexReader, HexWriter};
+#[cfg(feature = "alloc")]
+pub use plan::{advise, plan_message, Advice, CharCost, PartEncoding, PlannedPart};
+pub use push::{Decoder, Encoder, Padding, TakeChars};
+#[cfg(feature = "std")]
+pub use reader::{Gsm7BitReader, Gsm7BufReader, Gsm7Reader, Septets};
+#[cfg(feature = "alloc")]
+pub use pack::{pack_septets, unpack_septets};
+pub use segment::{
+    costs, fill_bits, header_septets, segment_count, text_septets, truncate_to_septets, udl, user_data_octets, Budget,
+};
+#[cfg(feature = "alloc")]
+pub use segment::split_for_sms;
+#[cfg(feature = "bytes")]
+pub use slice::{decode_buf, encode_buf};
+#[cfg(feature = "smallvec")]
+pub use slice::encode_small;
+#[cfg(feature = "heapless")]
+pub use slice::{decode_heapless, encode_heapless};
+pub use slice::{char_count, decode_into, encode_into, find_in_packed, is_valid_gsm7, nth_char, packed_eq_str,
+    CharIndices, Chars};
+#[cfg(feature = "alloc")]
+pub use slice::{decode, decode_to_string, decode_to_vec, encode};
+#[cfg(feature = "futures")]
+pub use stream::{Gsm7Sink, Gsm7Stream};
+#[cfg(feature = "alloc")]
+pub use string::Gsm7String;
+#[cfg(feature = "tokio")]
+pub use tokio_io::{AsyncGsm7Reader, AsyncGsm7Writer};
+#[cfg(feature = "alloc")]
+pub use transliterate::{map_greek, normalize_homoglyphs, normalize_line_endings, normalize_punctuation, LineEnding};
+#[cfg(feature = "downgrade")]
+pub use transliterate::{downgrade, Replacement};
+#[cfg(feature = "alloc")]
+pub use user_data::{DecodedUserData, DefaultAlphabet, EncodedUserData, UserDataDecoder, UserDataEncoder};
+#[cfg(feature = "std")]
+pub use writer::{Gsm7BitWriter, Gsm7Writer};
 
-pub struct Gsm7Reader<R: io::Read> {
-    reader: BitReader<R, Endianness>,
-}
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::io;
+
+    use crate::{Chars, Gsm7BufReader, Gsm7Error, Gsm7Reader, Gsm7String, Gsm7Writer, Septets};
+
+    #[test]
+    fn it_works() -> io::Result<()> {
+        let v = Vec::new();
+
+        let mut writer = Gsm7Writer::new(v);
+        writer.write_str("Hello")?;
+
+        let v = writer.into_writer()?;
+        eprintln!("v: {:?}", v);
+
+        let reader = Gsm7Reader::new(io::Cursor::new(&v));
+        let s = reader.collect::<io::Result<String>>()?;
+        assert_eq!(s.as_str(), "Hello");
 
-impl<R: io::Read> Gsm7Reader<R> {
-    pub fn new(reader: R) -> Self {
-        Self { reader: BitReader::new(reader) }
+        Ok(())
     }
-}
 
-impl<R: io::Read> From<BitReader<R, Endianness>> for Gsm7Reader<R> {
-    fn from(reader: BitReader<R, Endianness>) -> Self {
-        Self { reader }
+    #[test]
+    fn it_works_correctly() -> io::Result<()> {
+        let v: Vec<_> = vec![84, 58, 157, 14].into_iter().collect();
+        let reader = Gsm7Reader::new(io::Cursor::new(&v));
+        let s = reader.collect::<io::Result<String>>()?;
+        assert_eq!(&s, "Tttt");
+
+        let v = vec![0xD4, 0xF2, 0x9C, 0x0E];
+        let reader = Gsm7Reader::new(io::Cursor::new(&v));
+        let s: String = reader.collect::<io::Result<String>>()?;
+        assert_eq!(&s, "Test");
+
+        Ok(())
+    }
+
+    #[test]
+    fn size_hint_with_len() -> io::Result<()> {
+        let v = vec![0xD4, 0xF2, 0x9C, 0x0E];
+        let mut reader = Gsm7Reader::with_len(io::Cursor::new(&v), v.len());
+        assert_eq!(reader.size_hint(), (2, Some(4)));
+        reader.next().transpose()?;
+        assert_eq!(reader.size_hint(), (2, Some(3)));
+        assert_eq!(reader.collect::<io::Result<String>>()?, "est");
+
+        let reader = Gsm7Reader::new(io::Cursor::new(&v));
+        assert_eq!(reader.size_hint(), (0, None));
+
+        Ok(())
+    }
+
+    #[test]
+    fn udl_limits_decoding() -> io::Result<()> {
+        // Seven septets leave seven zero bits of padding, which would otherwise decode as '@'.
+        let mut writer = Gsm7Writer::new(Vec::new());
+        writer.write_str("ABCDEFG")?;
+        let v = writer.into_writer()?;
+        assert_eq!(v.len(), 7);
+        let s = Gsm7Reader::new(io::Cursor::new(&v)).collect::<io::Result<String>>()?;
+        assert_eq!(s, "ABCDEFG@");
+
+        let reader = Gsm7Reader::with_udl(io::Cursor::new(&v), 7);
+        assert_eq!(reader.size_hint(), (4, Some(7)));
+        assert_eq!(reader.collect::<io::Result<String>>()?, "ABCDEFG");
+
+        let septets = Septets::new(io::Cursor::new(&v), 7);
+        assert_eq!(septets.len(), 7);
+        assert_eq!(septets.collect::<io::Result<Vec<_>>>()?, b"ABCDEFG");
+
+        let mut reader = Gsm7Reader::with_udl(io::Cursor::new(&v), 9);
+        assert_eq!(reader.by_ref().take(8).count(), 8);
+        assert_eq!(reader.next().unwrap().unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        assert!(reader.next().is_none());
+
+        // Running out well short of the UDL fails once, and leaves the UDL as it was.
+        let mut reader = Gsm7Reader::with_udl(io::Cursor::new(&v), 20);
+        assert_eq!(reader.by_ref().take(8).count(), 8);
+        assert!(reader.next().unwrap().is_err());
+        assert_eq!(reader.size_hint(), (0, Some(0)));
+        assert!(reader.next().is_none());
+        assert_eq!(reader.seek_to_septet(21).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        reader.rewind()?;
+        assert_eq!(reader.size_hint(), (10, Some(20)));
+        assert_eq!(reader.filter(Result::is_err).count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn encode_const_matches_writer() -> io::Result<()> {
+        let cases: [(&[u8], &str); 3] = [
+            (&crate::gsm7!("LOW BATTERY"), "LOW BATTERY"),
+            (&crate::gsm7!("A"), "A"),
+            (&crate::gsm7!("Ñoño @ 5£"), "Ñoño @ 5£"),
+        ];
+        for &(packed, s) in &cases {
+            let mut writer = Gsm7Writer::new(Vec::new());
+            writer.write_str(s)?;
+            assert_eq!(packed, &writer.into_writer()?[..]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn extension_chars_round_trip() -> io::Result<()> {
+        let s = "\x0C^{}\\[~]|€";
+        let mut writer = Gsm7Writer::new(Vec::new());
+        writer.write_str(s)?;
+        let v = writer.into_writer()?;
+        assert_eq!(v.len(), 18);
+
+        let reader = Gsm7Reader::with_udl(io::Cursor::new(&v), 20);
+        assert_eq!(reader.collect::<io::Result<String>>()?, s);
+
+        Ok(())
     }
-}
 
-impl<R: io::Read> Iterator for Gsm7Reader<R> {
-    type Item = io::Result<char>;
+    #[test]
+    fn buf_reader_matches_reader() -> io::Result<()> {
+        let mut writer = Gsm7Writer::new(Vec::new());
+        writer.write_str(&"The quick brown fox {jumps} over the lazy dog. ".repeat(20))?;
+        let v = writer.into_writer()?;
+
+        let expected = Gsm7Reader::new(io::Cursor::new(&v)).collect::<io::Result<String>>()?;
+        for &capacity in &[1, 3, 7, 64, 4096] {
+            let reader = Gsm7BufReader::new(io::BufReader::with_capacity(capacity, &v[..]));
+            assert_eq!(reader.collect::<io::Result<String>>()?, expected);
+        }
 
-    fn next(&mut self) -> Option<Self::Item> {
-        let septet: u8 = match self.reader.read(7) {
-            Ok(s) => s,
-            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => return None,
-            Err(e) => return Some(Err(e)),
+        Ok(())
+    }
+
+    #[test]
+    fn no_panic_on_arbitrary_input() {
+        // xorshift, to get reproducible pseudo-random inputs without a dependency.
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
         };
 
-        if septet == ESC {
-            let septet: u8 = match self.reader.read(7) {
-                Ok(s) => s,
-                Err(e) => return Some(Err(e)),
-            };
-            Some(Ok(match septet {
-                0x0A => '\x0C',
-                0x14 => '^',
-                0x28 => '{',
-                0x29 => '}',
-                0x2F => '\\',
-                0x3C => '[',
-                0x3D => '~',
-                0x3E => ']',
-                0x40 => '|',
-                0x65 => '€',
-                _ => return Some(Err(io::ErrorKind::InvalidData.into())),
-            }))
-        }
-        else {
-            if let Some(c) = GSM7_CHARSET.get(septet as usize) {
-                Some(Ok(*c))
+        let mut inputs: Vec<Vec<u8>> = (0..=0xFFFFu16).map(|i| i.to_le_bytes().to_vec()).collect();
+        inputs.extend((0..2000).map(|_| (0..next() % 64).map(|_| next() as u8).collect()));
+        for v in &inputs {
+            for item in Gsm7Reader::new(&v[..]) {
+                let _ = item;
             }
-            else {
-                Some(Err(io::ErrorKind::InvalidData.into()))
+            for item in Gsm7Reader::with_udl(&v[..], (next() % 80) as usize) {
+                let _ = item;
+            }
+            for item in Gsm7BufReader::new(&v[..]) {
+                let _ = item;
+            }
+            for item in Septets::new(&v[..], (next() % 80) as usize) {
+                let _ = item;
+            }
+            for item in Chars::new(v) {
+                let _ = item;
+            }
+            for item in Chars::with_udl(v, (next() % 80) as usize) {
+                let _ = item;
+            }
+        }
+
+        for _ in 0..2000 {
+            let s: String = (0..next() % 64)
+                .filter_map(|_| std::char::from_u32(next() as u32 % 0x2100))
+                .collect();
+            let mut writer = Gsm7Writer::new(Vec::new());
+            let _ = writer.write_str(&s);
+            let _ = writer.into_writer();
+            let _ = crate::encode_into(&s, &mut [0; 16]);
+        }
+    }
+
+    #[test]
+    fn one_shot_encode() -> io::Result<()> {
+        assert_eq!(crate::encode("Test")?, vec![0xD4, 0xF2, 0x9C, 0x0E]);
+        #[cfg(feature = "smallvec")]
+        {
+            let small = crate::encode_small("Test")?;
+            assert!(!small.spilled());
+            assert_eq!(&small[..], &[0xD4, 0xF2, 0x9C, 0x0E]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn write_str_matches_write_char() -> io::Result<()> {
+        let long = "ASCII only ".repeat(9);
+        let cases = ["", "abc", "Hello, world! 123", "@home $5 ¿qué? [x] {y}\r\n_", "Ñuñoa øre", &long];
+        for s in &cases {
+            let mut bulk = Gsm7Writer::new(Vec::new());
+            bulk.write_str(s)?;
+            let mut per_char = Gsm7Writer::new(Vec::new());
+            for c in s.chars() {
+                per_char.write_char(c)?;
             }
+            assert_eq!(bulk.into_writer()?, per_char.into_writer()?);
+        }
+
+        #[cfg(feature = "ufmt")]
+        {
+            let mut writer = Gsm7Writer::new(Vec::new());
+            ufmt::uwriteln!(writer, "Battery {}%", 17u8)?;
+            assert_eq!(writer.into_writer()?, crate::encode("Battery 17%\n")?);
         }
+
+        Ok(())
     }
-}
 
-pub struct Gsm7Writer<W: io::Write> {
-    writer: BitWriter<W, Endianness>,
-    counter: usize,
-}
+    #[test]
+    fn lookup_covers_both_tables() {
+        use crate::charset::{lookup, Code, ESC, GSM7_CHARSET, GSM7_EXTENSION_CHARS};
 
-impl<W: io::Write> Gsm7Writer<W> {
-    pub fn new(writer: W) -> Self {
-        Self { writer: BitWriter::new(writer), counter: 0 }
+        for (septet, &c) in GSM7_CHARSET.iter().enumerate() {
+            let expected = if septet == ESC as usize {
+                Code::Unencodable
+            }
+            else {
+                Code::Basic(septet as u8)
+            };
+            assert_eq!(lookup(c), expected);
+        }
+        for &(c, septet) in &GSM7_EXTENSION_CHARS {
+            assert_eq!(lookup(c), Code::Escaped(septet));
+        }
+        assert_eq!(lookup('`'), Code::Unencodable);
+        assert_eq!(lookup('ú'), Code::Unencodable);
     }
 
-    pub fn write_bit(&mut self, bit: bool) -> io::Result<()> {
-        self.writer.write_bit(bit)?;
-        self.counter += 1;
+    #[test]
+    fn slice_api_matches_io_api() -> io::Result<()> {
+        for s in &["", "A", "Hello", "ABCDEFG", "ABCDEFGH", "{€} ¿Qué tal?", "\x0C^{}\\[~]|€"] {
+            let mut writer = Gsm7Writer::new(Vec::new());
+            writer.write_str(s)?;
+            let v = writer.into_writer()?;
+            assert_eq!(crate::encode(s)?, v);
+
+            let mut buf = [0; 32];
+            let len = crate::encode_into(s, &mut buf)?;
+            assert_eq!(&buf[..len], &v[..]);
+
+            let expected = Gsm7Reader::new(&v[..]).collect::<io::Result<String>>()?;
+            assert_eq!(crate::decode(&v)?, expected);
+        }
+
+        assert_eq!(crate::encode_into("Hello", &mut [0; 4]), Err(Gsm7Error::BufferTooSmall));
+        assert_eq!(crate::encode("ú"), Err(Gsm7Error::Unencodable('ú')));
+        assert_eq!(Chars::new(&[0x1B, 0x00]).next(), Some(Err(Gsm7Error::InvalidEscape(0))));
+        assert_eq!(Chars::with_udl(&[0x1B], 1).next(), Some(Err(Gsm7Error::TruncatedEscape)));
+
         Ok(())
     }
 
-    pub fn write<U>(&mut self, bits: u32, value: U) -> io::Result<()>
-    where
-        U: Numeric
-    {
-        self.writer.write(bits, value)?;
-        self.counter += bits as usize;
+    #[test]
+    fn fixed_capacity_buffers() -> io::Result<()> {
+        let v = crate::encode("Grüße")?;
+        let mut buf = [0; 7];
+        let len = crate::decode_into(&v, &mut buf)?;
+        assert_eq!(std::str::from_utf8(&buf[..len]).unwrap(), "Grüße");
+        assert_eq!(crate::decode_into(&v, &mut [0; 6]), Err(Gsm7Error::BufferTooSmall));
+
+        #[cfg(feature = "heapless")]
+        {
+            let packed = crate::encode_heapless::<5>("Grüße")?;
+            assert_eq!(&packed[..], &v[..]);
+            assert_eq!(crate::encode_heapless::<4>("Grüße"), Err(Gsm7Error::BufferTooSmall));
+            assert_eq!(crate::decode_heapless::<7>(&v)?, "Grüße");
+            assert_eq!(crate::decode_heapless::<6>(&v), Err(Gsm7Error::BufferTooSmall));
+        }
+
         Ok(())
     }
 
-    pub fn write_bytes(&mut self, buf: &[u8]) -> io::Result<()> {
-        self.writer.write_bytes(buf)
+    #[test]
+    fn gsm7_string_validates() {
+        let s: Gsm7String = "{Hello}".parse().unwrap();
+        assert_eq!(s.as_str(), "{Hello}");
+        assert_eq!(s.septet_len(), 9);
+        assert_eq!("Hellú".parse::<Gsm7String>().unwrap_err(), Gsm7Error::Unencodable('ú'));
+
+        #[cfg(feature = "serde")]
+        {
+            assert_eq!(serde_json::to_string(&s).unwrap(), r#""{Hello}""#);
+            let s: Gsm7String = serde_json::from_str(r#""€5""#).unwrap();
+            assert_eq!(s.septet_len(), 3);
+            assert!(serde_json::from_str::<Gsm7String>(r#""Hellú""#).is_err());
+        }
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn arbitrary_values_are_valid() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        use crate::{Alphabet, Budget, DecodedUserData, DefaultAlphabet, EncodedUserData, UserDataDecoder, UserDataEncoder};
+
+        let data: Vec<u8> = (0..=255).cycle().take(4096).collect();
+        let mut u = Unstructured::new(&data);
+        while !u.is_empty() {
+            let s = Gsm7String::arbitrary(&mut u).unwrap();
+            assert_eq!(s.as_str().parse::<Gsm7String>().unwrap().septet_len(), s.septet_len());
+        }
+
+        let mut u = Unstructured::new(&data);
+        while !u.is_empty() {
+            let ud = EncodedUserData::arbitrary(&mut u).unwrap();
+            assert!(crate::user_data::is_gsm7_dcs(ud.dcs));
+            assert!(ud.udl <= Budget::Single.septets());
+            assert_eq!(ud.user_data.len(), crate::user_data_octets(ud.udl));
+            let decoded = DecodedUserData::arbitrary(&mut u).unwrap();
+            let encoded = DefaultAlphabet.encode_user_data(&decoded.text, decoded.udh.as_deref()).unwrap();
+            let udhi = decoded.udh.is_some();
+            assert_eq!(DefaultAlphabet.decode_user_data(0x00, udhi, encoded.udl, &encoded.user_data), Ok(decoded));
+            assert!(Alphabet::all().any(|alphabet| alphabet == Alphabet::arbitrary(&mut u).unwrap()));
+        }
+    }
+
+    #[cfg(feature = "quickcheck")]
+    #[test]
+    fn arbitrary_gsm7_strings_have_exact_septet_lens() {
+        fn septet_len_is_exact(s: Gsm7String) -> bool {
+            crate::encode(s.as_str()).map(|v| v.len()) == Ok((s.septet_len() * 7).div_ceil(8))
+        }
+        quickcheck::quickcheck(septet_len_is_exact as fn(Gsm7String) -> bool);
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn buf_api_consumes_user_data() {
+        use bytes::{Buf, BufMut, BytesMut};
+
+        let mut frame = BytesMut::new();
+        frame.put_u8(9);
+        assert_eq!(crate::encode_buf("{Hello}", &mut frame), Ok(8));
+        frame.put_slice(b"rest");
+        let mut frame = frame.freeze();
+
+        let udl = frame.get_u8() as usize;
+        assert_eq!(crate::decode_buf(&mut frame, udl).unwrap(), "{Hello}");
+        assert_eq!(&frame[..], b"rest");
+        assert_eq!(crate::decode_buf(&mut frame, udl), Err(Gsm7Error::UnexpectedEnd));
+        assert_eq!(frame.remaining(), 4);
+
+        // Split across chunks.
+        let packed = crate::encode("Hello").unwrap();
+        let mut chain = (&packed[..2]).chain(&packed[2..]);
+        assert_eq!(crate::decode_buf(&mut chain, 5).unwrap(), "Hello");
+        assert!(!chain.has_remaining());
+
+        let mut small = [0; 4];
+        assert_eq!(crate::encode_buf("Hello", &mut &mut small[..]), Err(Gsm7Error::BufferTooSmall));
+    }
+
+    #[test]
+    fn push_decoder_handles_any_chunking() {
+        let text = "{Hello} €5 ".repeat(30);
+        let packed = crate::encode(&text).unwrap();
+        for chunk_len in 1..=9 {
+            let mut decoder = crate::Decoder::new();
+            let mut s = String::new();
+            for mut chunk in packed.chunks(chunk_len) {
+                while !chunk.is_empty() {
+                    chunk = &chunk[decoder.push_bytes(chunk)..];
+                    for c in decoder.take_chars() {
+                        s.push(c.unwrap());
+                    }
+                }
+            }
+            assert_eq!(decoder.finish(), Ok(()));
+            assert_eq!(s.trim_end_matches('\r'), text);
+        }
+
+        let mut decoder = crate::Decoder::with_udl(8);
+        assert_eq!(decoder.push_bytes(&packed), 7);
+        assert!(decoder.is_complete());
+        assert_eq!(decoder.take_chars().collect::<Result<String, _>>(), Ok("{Hello".into()));
+        // The eighth septet is the escape that starts '}'.
+        assert_eq!(decoder.finish(), Err(Gsm7Error::TruncatedEscape));
+
+        let mut decoder = crate::Decoder::new();
+        decoder.push_bytes(&[0x1B]);
+        assert_eq!(decoder.take_chars().next(), None);
+        assert_eq!(decoder.finish(), Err(Gsm7Error::TruncatedEscape));
     }
 
-    pub fn write_str(&mut self, s: &str) -> io::Result<()> {
-        for c in s.chars() {
-            self.write_char(c)?;
+    #[test]
+    fn push_encoder_pads_explicitly() -> Result<(), Gsm7Error> {
+        use crate::{Encoder, Padding};
+
+        let text = "{Hello} €5 ".repeat(30);
+        let mut encoder = Encoder::new();
+        let mut packed = Vec::new();
+        let mut rest = &text[..];
+        while !rest.is_empty() {
+            rest = &rest[encoder.push_str(rest).unwrap()..];
+            packed.extend_from_slice(encoder.take_bytes());
         }
+        packed.extend_from_slice(encoder.finish(Padding::Zero));
+        assert_eq!(packed, crate::encode(&text)?);
+
+        // Seven spare bits are filled with CR.
+        encoder.push_str("1234567").unwrap();
+        assert_eq!(encoder.finish(Padding::Cr), [0x31, 0xD9, 0x8C, 0x56, 0xB3, 0xDD, 0x1A]);
+        encoder.push_str("1234567").unwrap();
+        assert_eq!(encoder.finish(Padding::Zero), [0x31, 0xD9, 0x8C, 0x56, 0xB3, 0xDD, 0x00]);
+        // A CR that ends on a byte boundary gets a second CR.
+        encoder.push_str("1234567\r").unwrap();
+        assert_eq!(encoder.finish(Padding::Cr).len(), 8);
+        assert_eq!(encoder.push_char('ú'), Err(Gsm7Error::Unencodable('ú')));
         Ok(())
     }
 
-    pub fn write_char(&mut self, c: char) -> io::Result<()> {
-        match c {
-            '\x0C' => self.write_ext(0x0A)?,
-            '^' => self.write_ext(0x14)?,
-            '{' => self.write_ext(0x28)?,
-            '}' => self.write_ext(0x29)?,
-            '\\' => self.write_ext(0x2F)?,
-            '[' => self.write_ext(0x3C)?,
-            '~' => self.write_ext(0x3D)?,
-            ']' => self.write_ext(0x3E)?,
-            '|' => self.write_ext(0x40)?,
-            '€' => self.write_ext(0x65)?,
-            _ => if let Some(b) = GSM7_CHARSET.iter().position(|&v| v == c) {
-                self.writer.write(7, b as u8)?;
-                self.counter += 7;
+    #[test]
+    fn error_implements_core_error() {
+        let e: &dyn core::error::Error = &Gsm7Error::InvalidEscape(0x42);
+        assert_eq!(e.to_string(), "invalid extension septet 0x42");
+        assert!(e.source().is_none());
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn decode_failures_are_traced() {
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id, Record};
+        use tracing::{Event, Metadata};
+
+        // Records the `septet` field of every event.
+        struct Septets(Arc<Mutex<Vec<u64>>>);
+
+        impl Visit for Septets {
+            fn record_u64(&mut self, field: &Field, value: u64) {
+                if field.name() == "septet" {
+                    self.0.lock().unwrap().push(value);
+                }
             }
-            else {
-                return Err(io::ErrorKind::InvalidData.into());
+
+            fn record_debug(&mut self, _: &Field, _: &dyn std::fmt::Debug) {}
+        }
+
+        impl tracing::Subscriber for Septets {
+            fn enabled(&self, _: &Metadata<'_>) -> bool {
+                true
+            }
+
+            fn new_span(&self, _: &Attributes<'_>) -> Id {
+                Id::from_u64(1)
+            }
+
+            fn record(&self, _: &Id, _: &Record<'_>) {}
+
+            fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+            fn event(&self, event: &Event<'_>) {
+                event.record(&mut Septets(self.0.clone()));
             }
+
+            fn enter(&self, _: &Id) {}
+
+            fn exit(&self, _: &Id) {}
         }
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut writer = Gsm7Writer::new(Vec::new());
+        writer.write_str("abc").unwrap();
+        writer.write_u8_bits(7, 0x1B).unwrap();
+        writer.write_u8_bits(7, 0x01).unwrap();
+        let packed = writer.into_writer().unwrap();
+        tracing::subscriber::with_default(Septets(seen.clone()), || {
+            assert!(Chars::new(&packed).any(|c| c.is_err()));
+            assert!(Gsm7Reader::new(&packed[..]).any(|c| c.is_err()));
+            assert!(Gsm7BufReader::new(&packed[..]).any(|c| c.is_err()));
+        });
+        assert_eq!(*seen.lock().unwrap(), [3, 3, 3]);
+    }
+
+    #[test]
+    fn user_data_codec_handles_headers() {
+        use crate::{DefaultAlphabet, UserDataDecoder, UserDataEncoder};
+
+        // A six-byte header takes seven septets, leaving one fill bit before the text.
+        let udh = [0x00, 0x03, 0x2A, 0x02, 0x01];
+        let ud = DefaultAlphabet.encode_user_data("Hello €", Some(&udh)).unwrap();
+        assert_eq!((ud.dcs, ud.udl), (0x00, 15));
+        assert_eq!(&ud.user_data[..6], [0x05, 0x00, 0x03, 0x2A, 0x02, 0x01]);
+        assert_eq!(crate::pack::septet_at(&ud.user_data, 7), Some(b'H'));
+
+        let decoded = DefaultAlphabet.decode_user_data(ud.dcs, true, ud.udl, &ud.user_data).unwrap();
+        assert_eq!(decoded.text, "Hello €");
+        assert_eq!(decoded.udh.as_deref(), Some(&udh[..]));
+
+        let ud = DefaultAlphabet.encode_user_data("Hello", None).unwrap();
+        assert_eq!(ud.user_data, crate::encode("Hello").unwrap());
+        let decoded = DefaultAlphabet.decode_user_data(0xF1, false, ud.udl, &ud.user_data).unwrap();
+        assert_eq!((decoded.text.as_str(), decoded.udh), ("Hello", None));
+
+        let ucs2 = DefaultAlphabet.decode_user_data(0x08, false, 5, &ud.user_data);
+        assert_eq!(ucs2, Err(Gsm7Error::UnsupportedDcs(0x08)));
+        assert_eq!(DefaultAlphabet.decode_user_data(0x00, true, 5, &[0x09]), Err(Gsm7Error::UnexpectedEnd));
+    }
+
+    #[test]
+    fn hex_helpers_round_trip() {
+        assert_eq!(crate::encode_to_hex("Hello").unwrap(), "C8329BFD06");
+        assert_eq!(crate::decode_from_hex("c8329bfd06").unwrap(), "Hello");
+        assert_eq!(crate::decode_from_hex(" C8 32 9B\nFD 06\n").unwrap(), "Hello");
+        assert_eq!(crate::decode_from_hex("").unwrap(), "");
+        assert_eq!(crate::decode_from_hex("C83"), Err(Gsm7Error::InvalidHex));
+        assert_eq!(crate::decode_from_hex("C8XX"), Err(Gsm7Error::InvalidHex));
+        assert_eq!(crate::encode_to_hex("ú"), Err(Gsm7Error::Unencodable('ú')));
+    }
+
+    #[test]
+    fn hex_adapters_stream_packed_data() -> io::Result<()> {
+        use std::io::Read;
+
+        use crate::{HexReader, HexWriter};
+
+        let mut writer = Gsm7Writer::new(HexWriter::new(Vec::new()));
+        writer.write_str("Hello")?;
+        assert_eq!(writer.into_writer()?.into_inner(), b"C8329BFD06");
+
+        let log = io::Cursor::new("c8 32 9B\nfd06\n");
+        let s = Gsm7Reader::new(HexReader::new(log)).collect::<io::Result<String>>()?;
+        assert_eq!(s, "Hello");
+
+        // A one-byte buffer splits the digits of every pair across refills.
+        let log = io::BufReader::with_capacity(1, &b"C8 32"[..]);
+        let mut bytes = Vec::new();
+        HexReader::new(log).read_to_end(&mut bytes)?;
+        assert_eq!(bytes, [0xC8, 0x32]);
+
+        let mut reader = HexReader::new(&b"C83"[..]);
+        assert_eq!(reader.read_to_end(&mut Vec::new()).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        assert!(HexReader::new(&b"C8XX"[..]).read_to_end(&mut Vec::new()).is_err());
         Ok(())
     }
 
-    pub fn into_writer(mut self) -> io::Result<W> {
-        let remainder = self.counter % 8;
-        if remainder == 7 {
-            self.writer.write(7, 0x0D)?;
-        }
-        else if remainder != 0 {
-            self.writer.byte_align()?;
+    #[test]
+    fn explain_bytes_annotates_every_character() {
+        use crate::{explain_bytes, Issue, Table};
+
+        // "{Hello", whose seven spare bits read as '@'.
+        let packed = crate::encode("{Hello").unwrap();
+        let annotations = explain_bytes(&packed, None);
+        assert_eq!(annotations.len(), 7);
+        let brace = &annotations[0];
+        assert_eq!((brace.bits.clone(), &brace.septets[..]), (0..14, &[0x1B, 0x28][..]));
+        assert_eq!((brace.table, brace.char, brace.issue), (Table::Extension, Some('{'), None));
+        assert_eq!(annotations[1].bits, 14..21);
+        assert_eq!((annotations[6].char, annotations[6].issue), (Some('@'), Some(Issue::PossiblePadding)));
+        assert_eq!(explain_bytes(&packed, Some(7)).len(), 6);
+
+        // An escape before 'A', which has no extension meaning, then a lone escape.
+        let packed = [0x9B, 0xE0, 0x06];
+        let annotations = explain_bytes(&packed, Some(3));
+        assert_eq!((annotations[0].char, annotations[0].issue), (Some('A'), Some(Issue::InvalidEscape)));
+        assert_eq!((annotations[1].char, annotations[1].issue), (None, Some(Issue::TruncatedEscape)));
+
+        let annotations = explain_bytes(&[0xC8, 0x34], Some(4));
+        assert_eq!(annotations.iter().map(|a| a.char).collect::<Vec<_>>(), [Some('H'), Some('i'), None]);
+        assert_eq!((annotations[2].bits.clone(), annotations[2].issue), (14..28, Some(Issue::UnexpectedEnd)));
+    }
+
+    #[test]
+    fn decode_with_report_keeps_going() {
+        use crate::{decode_with_report, DecodeIssue, Issue};
+
+        assert_eq!(decode_with_report(&crate::encode("Hello").unwrap()), ("Hello".to_owned(), vec![]));
+
+        // An escape before 'A', which has no extension meaning, then a lone escape.
+        let (text, issues) = decode_with_report(&[0x9B, 0xE0, 0x06]);
+        assert_eq!(crate::decode(&[0x9B, 0xE0, 0x06]), Err(Gsm7Error::InvalidEscape(0x41)));
+        assert_eq!(text, "A");
+        assert_eq!(issues, [
+            DecodeIssue { septet: 0, issue: Issue::InvalidEscape },
+            DecodeIssue { septet: 2, issue: Issue::TruncatedEscape },
+        ]);
+
+        let (text, issues) = decode_with_report(&crate::encode("1234567").unwrap());
+        assert_eq!(text, "1234567@");
+        assert_eq!(issues, [DecodeIssue { septet: 7, issue: Issue::PossiblePadding }]);
+    }
+
+    #[test]
+    fn truncated_escape_follows_policy() -> io::Result<()> {
+        use crate::TruncatedEscapePolicy;
+
+        // "Hi" then an escape.
+        let packed = [0xC8, 0xF4, 0x06];
+        let reader = Gsm7Reader::with_udl(&packed[..], 3);
+        let e = reader.collect::<io::Result<String>>().unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        let reader = Gsm7Reader::with_udl(&packed[..], 3).truncated_escape(TruncatedEscapePolicy::Space);
+        assert_eq!(reader.collect::<io::Result<String>>()?, "Hi ");
+
+        let reader = Gsm7BufReader::new(&packed[..]).truncated_escape(TruncatedEscapePolicy::Space);
+        assert_eq!(reader.collect::<io::Result<String>>()?, "Hi ");
+        let chars = Chars::with_udl(&packed, 3);
+        assert_eq!(chars.collect::<Result<String, _>>(), Err(Gsm7Error::TruncatedEscape));
+        let chars = Chars::with_udl(&packed, 3).truncated_escape(TruncatedEscapePolicy::Space);
+        assert_eq!(chars.collect::<Result<String, _>>(), Ok("Hi ".into()));
+        Ok(())
+    }
+
+    #[test]
+    fn normalize_punctuation_makes_text_encodable() {
+        use std::borrow::Cow;
+
+        use crate::normalize_punctuation;
+
+        let pasted = "\u{201C}Don\u{2019}t miss out\u{201D} \u{2013} 50%\u{00A0}off\u{2026}\u{200B}";
+        assert!(crate::encode(pasted).is_err());
+        let normalized = normalize_punctuation(pasted);
+        assert_eq!(normalized, "\"Don't miss out\" - 50% off...");
+        assert!(crate::encode(&normalized).is_ok());
+
+        assert!(matches!(normalize_punctuation("plain ü text"), Cow::Borrowed("plain ü text")));
+        assert_eq!(normalize_punctuation("ú\u{2014}"), "ú-");
+    }
+
+    #[cfg(feature = "downgrade")]
+    #[test]
+    fn downgrade_avoids_ucs2() {
+        use crate::{downgrade, Replacement};
+
+        let (text, replacements) = downgrade("Café ú ç \u{2019} \u{E000} é");
+        assert_eq!(text, "Café u c ' ? é");
+        assert!(crate::encode(&text).is_ok());
+        assert_eq!(replacements[0], Replacement { offset: 6, original: 'ú', replacement: "u" });
+        assert_eq!(replacements.iter().map(|r| r.original).collect::<String>(), "úç\u{2019}\u{E000}");
+        assert_eq!(downgrade("Hello"), ("Hello".to_owned(), vec![]));
+    }
+
+    #[cfg(feature = "nfc")]
+    #[test]
+    fn encoding_composes_to_nfc() -> io::Result<()> {
+        let decomposed = "Caf\u{65}\u{301}";
+        assert_eq!(crate::encode(decomposed)?, crate::encode("Café")?);
+        assert_eq!(crate::segment_count(decomposed), Ok(1));
+
+        let mut writer = Gsm7Writer::new(Vec::new());
+        writer.write_str(decomposed)?;
+        assert_eq!(crate::decode(&writer.into_writer()?)?, "Café");
+        // Combining accents with no precomposed form in the table still fail.
+        assert_eq!(crate::encode("u\u{301}"), Err(Gsm7Error::Unencodable('ú')));
+        Ok(())
+    }
+
+    #[test]
+    fn map_greek_uses_table_glyphs() {
+        use crate::map_greek;
+
+        let greek = "ΚΑΛΗΜΕΡΑ ΣΑΣ, Ώρα: 5\u{2126}";
+        assert!(crate::encode(greek).is_err());
+        assert_eq!(map_greek(greek), "KAΛHMEPA ΣAΣ, Ωρα: 5Ω");
+        assert_eq!(crate::encode(&map_greek(&"Καλημέρα".to_uppercase())).unwrap(), crate::encode("KAΛHMEPA").unwrap());
+        assert_eq!(crate::encode(&map_greek("Καλημέρα")), Err(Gsm7Error::Unencodable('α')));
+    }
+
+    #[test]
+    fn submit_pdus_concatenate_long_text() {
+        use crate::pdu::{submit_pdus, Error};
+        use crate::{DefaultAlphabet, UserDataDecoder};
+
+        let pdus = submit_pdus("+46708251358", "hellohello", 7).unwrap();
+        assert_eq!(pdus, [vec![
+            0x01, 0x00, 0x0B, 0x91, 0x64, 0x07, 0x28, 0x15, 0x53, 0xF8, 0x00, 0x00, 0x0A, 0xE8, 0x32, 0x9B,
+            0xFD, 0x46, 0x97, 0xD9, 0xEC, 0x37,
+        ]]);
+
+        let text = format!("{}{}", "a".repeat(152), "€".repeat(10));
+        let pdus = submit_pdus("4479", &text, 0x2A).unwrap();
+        assert_eq!(pdus.len(), 2);
+        let mut decoded = String::new();
+        for (seq, pdu) in (1..).zip(&pdus) {
+            assert_eq!(&pdu[..6], [0x41, 0x00, 0x04, 0x81, 0x44, 0x97]);
+            let ud = DefaultAlphabet.decode_user_data(pdu[7], true, pdu[8] as usize, &pdu[9..]).unwrap();
+            assert_eq!(ud.udh.as_deref(), Some(&[0x00, 0x03, 0x2A, 0x02, seq][..]));
+            decoded += &ud.text;
         }
-        Ok(self.writer.into_writer())
+        // The first escape pair doesn't fit in the 153rd septet, so the first part has 152.
+        assert_eq!(pdus[0][8], 152 + 7);
+        assert_eq!(decoded, text);
+
+        assert_eq!(submit_pdus("+44 79", "hi", 0), Err(Error::InvalidAddress));
+        assert_eq!(submit_pdus("+", "hi", 0), Err(Error::InvalidAddress));
+        assert_eq!(submit_pdus("4479", "ú", 0), Err(Error::Gsm7(Gsm7Error::Unencodable('ú'))));
+        assert_eq!(submit_pdus("4479", &"a".repeat(153 * 256), 0), Err(Error::TooManySegments));
     }
 
-    fn write_ext(&mut self, b: u8) -> io::Result<()> {
-        self.writer.write(7, 0x1B)?;
-        self.writer.write(7, b)?;
-        self.counter += 14;
+    #[test]
+    fn costs_count_extension_chars_double() {
+        let costs = crate::costs("a€ú").collect::<Vec<_>>();
+        assert_eq!(costs, [('a', Some(1)), ('€', Some(2)), ('ú', None)]);
+        assert_eq!(crate::costs("").count(), 0);
+    }
+
+    #[test]
+    fn extension_chars_are_listed() {
+        use crate::{is_extension_char, EXTENSION_CHARS};
+
+        assert_eq!(EXTENSION_CHARS.iter().collect::<String>(), "\x0C[\\]^{|}~€");
+        assert!(EXTENSION_CHARS.iter().all(|&c| is_extension_char(c)));
+        assert!(!is_extension_char('a'));
+        assert!(!is_extension_char('ú'));
+    }
+
+    #[test]
+    fn truncate_to_septets_keeps_escape_pairs_whole() {
+        use crate::truncate_to_septets;
+
+        assert_eq!(truncate_to_septets("Hello", 160), Ok("Hello"));
+        assert_eq!(truncate_to_septets("Hello", 3), Ok("Hel"));
+        assert_eq!(truncate_to_septets("ab€", 3), Ok("ab"));
+        assert_eq!(truncate_to_septets("ab€", 4), Ok("ab€"));
+        assert_eq!(truncate_to_septets("ab", 0), Ok(""));
+        assert_eq!(truncate_to_septets("aú", 5), Err(Gsm7Error::Unencodable('ú')));
+    }
+
+    #[test]
+    fn split_for_sms_matches_segment_count() {
+        use crate::split_for_sms;
+
+        assert_eq!(split_for_sms(""), Ok(vec![""]));
+        let text = "a".repeat(160);
+        assert_eq!(split_for_sms(&text), Ok(vec![&text[..]]));
+
+        let text = format!("{}{}", "a".repeat(152), "€".repeat(80));
+        let parts = split_for_sms(&text).unwrap();
+        assert_eq!(parts.len(), crate::segment_count(&text).unwrap());
+        assert_eq!(parts.iter().map(|p| crate::costs(p).map(|(_, n)| n.unwrap() as usize).sum()).collect::<Vec<usize>>(), [
+            152, 152, 8
+        ]);
+        assert_eq!(parts.concat(), text);
+        assert_eq!(split_for_sms("ú"), Err(Gsm7Error::Unencodable('ú')));
+    }
+
+    #[test]
+    fn writer_stops_at_septet_budget() -> io::Result<()> {
+        let mut writer = Gsm7Writer::with_capacity_septets(Vec::new(), 4);
+        let e = writer.write_str("abc€").unwrap_err();
+        assert_eq!(e.get_ref().and_then(|e| e.downcast_ref()), Some(&Gsm7Error::WouldOverflow));
+        writer.write_char('d')?;
+        assert!(writer.write_char('e').is_err());
+        assert_eq!(crate::decode(&writer.into_writer()?)?, "abcd");
+
+        let mut writer = Gsm7Writer::with_capacity_septets(Vec::new(), 160);
+        writer.write_str(&"a".repeat(160))?;
+        assert!(writer.write_str("a").is_err());
+        assert!(Gsm7Writer::new(Vec::new()).write_str(&"a".repeat(161)).is_ok());
         Ok(())
     }
-}
 
-impl<W: io::Write> From<BitWriter<W, Endianness>> for Gsm7Writer<W> {
-    fn from(writer: BitWriter<W, Endianness>) -> Self {
-        Self { writer, counter: 0 }
+    #[test]
+    fn try_write_str_reports_where_it_stopped() -> io::Result<()> {
+        let text = "abc€def";
+        let mut first = Gsm7Writer::with_capacity_septets(Vec::new(), 4);
+        let n = first.try_write_str(text)?;
+        assert_eq!(&text[..n], "abc");
+        let mut second = Gsm7Writer::with_capacity_septets(Vec::new(), 4);
+        let m = second.try_write_str(&text[n..])?;
+        assert_eq!(&text[n..][m..], "f");
+        assert_eq!(crate::decode(&first.into_writer()?)?, "abc");
+        assert_eq!(crate::decode(&second.into_writer()?)?, "€de");
+
+        let mut writer = Gsm7Writer::new(Vec::new());
+        assert_eq!(writer.try_write_str("Hi ú")?, 3);
+        assert_eq!(writer.try_write_str("")?, 0);
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::io;
+    #[test]
+    fn reader_seeks_by_septet_and_char() -> io::Result<()> {
+        use bitstream_io::{BitRead, BitReader, LittleEndian};
+
+        use crate::{DefaultAlphabet, UserDataEncoder};
+
+        let packed = crate::encode("{Hello} world")?;
+        let mut reader = Gsm7Reader::with_udl(io::Cursor::new(&packed), 15);
+        reader.seek_to_septet(7)?;
+        assert_eq!(reader.collect::<io::Result<String>>()?, "} world");
 
-    use crate::{Gsm7Reader, Gsm7Writer};
+        let mut reader = Gsm7Reader::with_udl(io::Cursor::new(&packed), 15);
+        reader.seek_to_char(6)?;
+        assert_eq!(reader.next().transpose()?, Some('}'));
+        reader.seek_to_septet(3)?;
+        assert_eq!(reader.collect::<io::Result<String>>()?, "ello} world");
+
+        // Records are whole bytes, so the second of two 7-byte records starts at septet 8.
+        let records = [crate::encode("1234567")?, crate::encode("abcdefg")?].concat();
+        let mut reader = Gsm7Reader::new(io::Cursor::new(&records));
+        reader.seek_to_septet(8)?;
+        assert_eq!(reader.take(7).collect::<io::Result<String>>()?, "abcdefg");
+
+        // Text after a six-octet UDH starts after one fill bit, in the source's seventh byte.
+        let ud = DefaultAlphabet.encode_user_data("Seek me", Some(&[0x00, 0x03, 0x01, 0x02, 0x01]))?;
+        let mut source = io::Cursor::new(&ud.user_data);
+        source.set_position(6);
+        let mut bits = BitReader::endian(source, LittleEndian);
+        bits.skip(1)?;
+        let mut reader = Gsm7Reader::from(bits).udl(7);
+        assert_eq!(reader.by_ref().take(3).collect::<io::Result<String>>()?, "See");
+        reader.seek_to_septet(5)?;
+        assert_eq!(reader.next().transpose()?, Some('m'));
+        reader.rewind()?;
+        assert_eq!(reader.collect::<io::Result<String>>()?, "Seek me");
+
+        let mut reader = Gsm7Reader::with_udl(io::Cursor::new(&packed), 15);
+        assert_eq!(reader.seek_to_septet(16).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(reader.seek_to_char(20).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+        Ok(())
+    }
 
     #[test]
-    fn it_works() -> io::Result<()> {
-        let v = Vec::new();
+    fn readers_rewind_and_reset() -> io::Result<()> {
+        let hello = crate::encode("Hello")?;
+        let world = crate::encode("world")?;
 
-        let mut writer = Gsm7Writer::new(v);
+        let mut reader = Gsm7Reader::with_udl(io::Cursor::new(&hello[..]), 5);
+        assert_eq!(reader.by_ref().take(3).collect::<io::Result<String>>()?, "Hel");
+        reader.rewind()?;
+        assert_eq!(reader.by_ref().collect::<io::Result<String>>()?, "Hello");
+        reader.reset(io::Cursor::new(&world[..]));
+        assert_eq!(reader.collect::<io::Result<String>>()?, "world");
+
+        let mut reader = Gsm7BufReader::new(io::BufReader::with_capacity(2, io::Cursor::new(&hello[..])));
+        assert_eq!(reader.by_ref().take(4).collect::<io::Result<String>>()?, "Hell");
+        reader.rewind()?;
+        assert_eq!(reader.by_ref().collect::<io::Result<String>>()?, "Hello");
+        let old = reader.reset(io::BufReader::new(io::Cursor::new(&world[..])));
+        assert_eq!(old.into_inner().position(), 5);
+        assert_eq!(reader.collect::<io::Result<String>>()?, "world");
+        Ok(())
+    }
+
+    #[test]
+    fn chars_decode_from_either_end() {
+        let text = "{Hello} [world]";
+        let packed = crate::encode(text).unwrap();
+        let udl = crate::costs(text).map(|(_, n)| n.unwrap() as usize).sum();
+        let backwards = Chars::with_udl(&packed, udl).rev().collect::<Result<String, _>>().unwrap();
+        assert_eq!(backwards, text.chars().rev().collect::<String>());
+
+        let mut chars = Chars::with_udl(&packed, udl);
+        assert_eq!((chars.next(), chars.next_back()), (Some(Ok('{')), Some(Ok(']'))));
+        assert_eq!(chars.rev().take(5).collect::<Result<String, _>>(), Ok("dlrow".into()));
+
+        // Two escapes make an invalid pair, and a third is left on its own.
+        let escapes = [0x9B, 0xCD, 0x06];
+        assert_eq!(Chars::with_udl(&escapes, 3).collect::<Vec<_>>(), [
+            Err(Gsm7Error::InvalidEscape(0x1B)),
+            Err(Gsm7Error::TruncatedEscape),
+        ]);
+        assert_eq!(Chars::with_udl(&escapes, 3).rev().collect::<Vec<_>>(), [
+            Err(Gsm7Error::TruncatedEscape),
+            Err(Gsm7Error::InvalidEscape(0x1B)),
+        ]);
+        assert_eq!(Chars::with_udl(&[0xC8], 2).next_back(), Some(Err(Gsm7Error::UnexpectedEnd)));
+    }
+
+    #[test]
+    fn nth_char_skips_escape_pairs() {
+        let packed = crate::encode("{STOP}").unwrap();
+        assert_eq!(crate::nth_char(&packed, Some(8), 0), Some(Ok('{')));
+        assert_eq!(crate::nth_char(&packed, Some(8), 1), Some(Ok('S')));
+        assert_eq!(crate::nth_char(&packed, Some(8), 5), Some(Ok('}')));
+        assert_eq!(crate::nth_char(&packed, Some(8), 6), None);
+        assert_eq!(crate::nth_char(&packed, None, 6), None);
+        assert_eq!(crate::nth_char(&packed, Some(9), 6), Some(Err(Gsm7Error::UnexpectedEnd)));
+        assert_eq!(Chars::new(&packed).nth(2), Chars::new(&packed).collect::<Vec<_>>().get(2).cloned());
+    }
+
+    #[test]
+    fn nth_char_fails_where_next_does() {
+        use crate::{ControlPolicy, TruncatedEscapePolicy};
+
+        // A UDL that ends just after the escape of '}'.
+        let packed = crate::encode("{STOP}").unwrap();
+        assert_eq!(crate::nth_char(&packed, Some(7), 6), Some(Err(Gsm7Error::TruncatedEscape)));
+        let chars = Chars::with_udl(&packed, 7).truncated_escape(TruncatedEscapePolicy::Space);
+        assert_eq!(chars.clone().nth(6), None);
+        assert_eq!(chars.clone().nth(5), Some(Ok(' ')));
+
+        // An escape before 0x41, which has no meaning in the extension table.
+        assert_eq!(crate::nth_char(&[0x9B, 0x20], None, 1), Some(Err(Gsm7Error::InvalidEscape(0x41))));
+
+        let packed = crate::encode("a\nb").unwrap();
+        let chars = Chars::new(&packed).control_chars(ControlPolicy::Reject, "");
+        assert_eq!(chars.clone().nth(2), Some(Err(Gsm7Error::ControlChar('\n'))));
+        assert_eq!(Chars::new(&packed).control_chars(ControlPolicy::Replace(' '), "").nth(1), Some(Ok(' ')));
+    }
+
+    #[test]
+    fn writer_finishes_messages_in_place() -> io::Result<()> {
+        use crate::Padding;
+
+        let mut writer = Gsm7Writer::with_capacity_septets(Vec::new(), 8);
+        writer.write_str("1234567")?;
+        // Seven septets leave seven spare bits, which CR padding fills.
+        assert_eq!(writer.finish(Padding::Cr)?, 7);
+        writer.start_new_message()?;
         writer.write_str("Hello")?;
+        assert_eq!(writer.finish(Padding::Zero)?, 5);
+        writer.start_new_message()?;
+        writer.write_str("1234567\r")?;
+        assert_eq!(writer.finish(Padding::Cr)?, 8);
 
-        let v = writer.into_writer()?;
-        eprintln!("v: {:?}", v);
+        let out = writer.into_writer()?;
+        assert_eq!(crate::decode(&out[..7])?, "1234567\r");
+        assert_eq!(crate::decode(&out[7..12])?, "Hello");
+        assert_eq!(crate::decode(&out[12..])?, "1234567\r\r");
+        assert_eq!(crate::encode("Hello")?, out[7..12]);
+        Ok(())
+    }
 
-        let reader = Gsm7Reader::new(io::Cursor::new(&v));
-        let s = reader.collect::<io::Result<String>>()?;
-        assert_eq!(s.as_str(), "Hello");
+    #[test]
+    fn septets_pack_without_a_table() {
+        use crate::{pack_septets, unpack_septets};
+
+        let septets = [0x48, 0x65, 0x6C, 0x6C, 0x6F];
+        assert_eq!(pack_septets(&septets), crate::encode("Hello").unwrap());
+        assert_eq!(unpack_septets(&pack_septets(&septets)), septets);
+        // Eight septets fill seven octets exactly, and seven leave a zero septet at the end.
+        assert_eq!(pack_septets(&[0x7F; 8]), [0xFF; 7]);
+        assert_eq!(unpack_septets(&pack_septets(&[0x7F; 7])), [0x7F, 0x7F, 0x7F, 0x7F, 0x7F, 0x7F, 0x7F, 0]);
+        assert_eq!(pack_septets(&[0xFF]), [0x7F]);
+        assert!(pack_septets(&[]).is_empty() && unpack_septets(&[]).is_empty());
+    }
+
+    #[test]
+    fn packed_eq_str_compares_without_decoding_first() {
+        use crate::packed_eq_str;
+
+        let packed = crate::encode("{Hello}").unwrap();
+        assert!(packed_eq_str(&packed, "{Hello}", Some(9)));
+        assert!(!packed_eq_str(&packed, "{Hello", Some(9)));
+        assert!(!packed_eq_str(&packed, "{Hello}!", Some(9)));
+        assert!(packed_eq_str(&packed, "{Hello", Some(7)));
+        assert!(!packed_eq_str(&packed, "{Hello}", Some(10)));
+        assert!(packed_eq_str(&[], "", None));
+        // An invalid escape matches nothing, not even the basic reading of its septet.
+        assert!(!packed_eq_str(&[0x9B, 0x20], "A", Some(2)));
+    }
+
+    #[test]
+    fn find_in_packed_returns_char_index() {
+        use crate::find_in_packed;
+
+        let packed = crate::encode("Reply {STOP} to opt out").unwrap();
+        assert_eq!(find_in_packed(&packed, "STOP"), Some(7));
+        assert_eq!(find_in_packed(&packed, "{STOP}"), Some(6));
+        assert_eq!(find_in_packed(&packed, "Reply"), Some(0));
+        assert_eq!(find_in_packed(&packed, "out"), Some(20));
+        assert_eq!(find_in_packed(&packed, "STOPP"), None);
+        assert_eq!(find_in_packed(&packed, ""), Some(0));
+        assert_eq!(find_in_packed(&[], "a"), None);
+    }
+
+    #[test]
+    fn is_valid_gsm7_checks_escapes_and_length() {
+        use crate::is_valid_gsm7;
+
+        let packed = crate::encode("{Hello}").unwrap();
+        assert!(is_valid_gsm7(&packed, None));
+        assert!(is_valid_gsm7(&packed, Some(9)));
+        assert!(!is_valid_gsm7(&packed, Some(10)));
+        assert!(!is_valid_gsm7(&packed, Some(8)));
+        assert!(!is_valid_gsm7(&[0x9B, 0x20], Some(2)));
+        assert!(is_valid_gsm7(&[], None));
+    }
+
+    #[test]
+    fn char_count_counts_escape_pairs_once() {
+        let packed = crate::encode("{Hello}").unwrap();
+        assert_eq!(crate::char_count(&packed, Some(9)), Ok(7));
+        assert_eq!(crate::char_count(&packed, Some(3)), Ok(2));
+        assert_eq!(crate::char_count(&packed, Some(10)), Err(Gsm7Error::UnexpectedEnd));
+        assert_eq!(crate::char_count(&crate::encode("Hello").unwrap(), None), Ok(5));
+        assert_eq!(crate::char_count(&[], None), Ok(0));
+    }
+
+    #[test]
+    fn decode_appends_utf8_to_caller_buffers() {
+        let mut out = String::from("> ");
+        crate::decode_to_string(&crate::encode("{Øre} £5").unwrap(), Some(10), &mut out).unwrap();
+        assert_eq!(out, "> {Øre} £5");
+
+        let mut out = b"x".to_vec();
+        assert_eq!(crate::decode_to_vec(&[0x9B, 0x20], Some(2), &mut out), Err(Gsm7Error::InvalidEscape(0x41)));
+        assert_eq!(crate::decode_to_vec(&[0xC8], Some(2), &mut out), Err(Gsm7Error::UnexpectedEnd));
+        assert_eq!(crate::decode_to_vec(&[0x1B], Some(1), &mut out), Err(Gsm7Error::TruncatedEscape));
+        assert_eq!(out, b"x");
+
+        for s in ["", "Hello", "ΔΦΓ ¥€ 12345678"] {
+            let packed = crate::encode(s).unwrap();
+            let mut out = Vec::new();
+            crate::decode_to_vec(&packed, None, &mut out).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), crate::decode(&packed).unwrap());
+        }
+    }
+
+    #[test]
+    fn char_indices_yield_septet_offsets() {
+        let packed = crate::encode("a€b{").unwrap();
+        let indices: Vec<_> = Chars::new(&packed).char_indices().collect::<Result<_, _>>().unwrap();
+        assert_eq!(indices, vec![(0, 'a'), (1, '€'), (3, 'b'), (4, '{')]);
+        let mut back = Chars::with_udl(&packed, 6).char_indices();
+        assert_eq!(back.next_back(), Some(Ok((4, '{'))));
+        assert_eq!(back.next(), Some(Ok((0, 'a'))));
+        assert_eq!(back.offset(), 1);
+        assert_eq!(back.map(Result::unwrap).collect::<Vec<_>>(), vec![(1, '€'), (3, 'b')]);
+    }
+
+    #[test]
+    fn reader_errors_carry_offsets() {
+        let packed = crate::pack_septets(&[0x61, 0x62, 0x63, 0x1B, 0x41]);
+        let e = Gsm7Reader::new(&packed[..]).collect::<io::Result<String>>().unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        let e = e.get_ref().unwrap().downcast_ref::<crate::DecodeError>().unwrap();
+        assert_eq!((e.error, e.septet, e.bit()), (Gsm7Error::InvalidEscape(0x41), 3, 21));
+        assert_eq!(e.to_string(), "invalid extension septet 0x41 at septet 3 (bit 21)");
+
+        let e = Gsm7BufReader::new(&packed[..]).collect::<io::Result<String>>().unwrap_err();
+        assert_eq!(e.get_ref().unwrap().downcast_ref::<crate::DecodeError>().unwrap().septet, 3);
+
+        let e = Gsm7Reader::with_udl(&packed[..2], 4).collect::<io::Result<String>>().unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+        let e = e.into_inner().unwrap().downcast::<crate::DecodeError>().unwrap();
+        assert_eq!((e.error, e.septet), (Gsm7Error::UnexpectedEnd, 2));
+    }
+
+    #[test]
+    fn detect_encoding_tells_encodings_apart() {
+        use crate::{detect_encoding, Guess};
+        let ucs2 = |s: &str| s.encode_utf16().flat_map(u16::to_be_bytes).collect::<Vec<_>>();
+        assert_eq!(detect_encoding(&crate::encode("Meet me at the station at 9").unwrap()), Guess::PackedGsm7);
+        assert_eq!(detect_encoding(b"caff\x05 o \x10 tra noi"), Guess::UnpackedGsm7);
+        assert_eq!(detect_encoding(&ucs2("Привет, как дела?")), Guess::Ucs2);
+        assert_eq!(detect_encoding(&ucs2("Hello")), Guess::Ucs2);
+        assert_eq!(detect_encoding(b"Meet me at 9\r\n"), Guess::Utf8);
+        assert_eq!(detect_encoding("Grüße aus Köln".as_bytes()), Guess::Utf8);
+        assert_eq!(detect_encoding(&[0xFF, 0xFE, 0x80, 0x81, 0x9F]), Guess::Unknown);
+        assert_eq!(detect_encoding(&[]), Guess::Unknown);
+    }
+
+    #[test]
+    fn budgets_match_the_standard_limits() {
+        use crate::Budget;
+        let limits =
+            [Budget::Single, Budget::Concat8, Budget::Concat16].map(|b| (b.septets(), b.octets(), b.ucs2_chars()));
+        assert_eq!(limits, [(160, 140, 70), (153, 134, 67), (152, 133, 66)]);
+        let text = "x".repeat(151) + "{";
+        assert_eq!(Budget::Single.fits(&text), Ok(true));
+        assert_eq!(Budget::Concat8.fits(&text), Ok(true));
+        assert_eq!(Budget::Concat16.fits(&text), Ok(false));
+        assert_eq!(Budget::Single.fits("√"), Err(Gsm7Error::Unencodable('√')));
+
+        let mut writer = Gsm7Writer::with_budget(Vec::new(), Budget::Concat16);
+        let e = writer.write_str(&text).unwrap_err();
+        assert_eq!(e.into_inner().unwrap().downcast::<Gsm7Error>().unwrap(), Box::new(Gsm7Error::WouldOverflow));
+    }
+
+    #[test]
+    fn resumed_writer_matches_one_pass() {
+        let mut writer = Gsm7Writer::new(Vec::new());
+        writer.write_u8_bits(8, 0xAB).unwrap();
+        writer.write_u8_bits(5, 0x15).unwrap();
+        writer.write_str("hello").unwrap();
+        let len = writer.finish(crate::Padding::Cr).unwrap();
+        let whole = writer.into_writer().unwrap();
+
+        let mut writer = Gsm7Writer::resume(vec![0xAB], 13, 0xF5).unwrap();
+        writer.write_str("hello").unwrap();
+        assert_eq!(writer.finish(crate::Padding::Cr).unwrap(), len);
+        assert_eq!(writer.into_writer().unwrap(), whole);
+    }
+
+    #[test]
+    fn readers_and_writers_debug_and_clone() {
+        let packed = crate::encode("hello").unwrap();
+        let mut reader = Gsm7Reader::with_udl(&packed[..], 5);
+        reader.next();
+        assert_eq!(
+            format!("{:?}", reader),
+            "Gsm7Reader { septets_read: 1, bits_remaining: None, septets_remaining: Some(4), byte_aligned: false, \
+             truncated_escape: Error, .. }"
+        );
+
+        let mut writer = Gsm7Writer::with_capacity_septets(Vec::new(), 10);
+        writer.write_str("hi").unwrap();
+        assert_eq!(
+            format!("{:?}", writer),
+            "Gsm7Writer { bits: 14, capacity: Some(10), remaining: Some(8), byte_aligned: false, .. }"
+        );
+
+        let mut reader = Gsm7BufReader::new(&packed[..]);
+        reader.next();
+        let copy = reader.clone();
+        assert_eq!(reader.collect::<io::Result<String>>().unwrap(), "ello");
+        assert_eq!(copy.collect::<io::Result<String>>().unwrap(), "ello");
+    }
+
+    #[test]
+    fn gsm7_string_mutation_tracks_septets() {
+        let mut s = Gsm7String::new();
+        s.push('a').unwrap();
+        s.push_str("{b}").unwrap();
+        assert_eq!((s.as_str(), s.septet_len()), ("a{b}", 6));
+        assert_eq!(s.push('√'), Err(Gsm7Error::Unencodable('√')));
+        assert_eq!(s.push_str("c√"), Err(Gsm7Error::Unencodable('√')));
+        assert_eq!((s.as_str(), s.septet_len()), ("a{b}", 6));
+        s.truncate_septets(5);
+        assert_eq!((s.as_str(), s.septet_len()), ("a{b", 4));
+        s.truncate_septets(2);
+        assert_eq!((s.as_str(), s.septet_len()), ("a", 1));
+        s.clear();
+        assert_eq!((s.as_str(), s.septet_len()), ("", 0));
+    }
+
+    #[test]
+    fn array_string_packs_inline() {
+        let mut s = crate::Gsm7ArrayString::<7>::new();
+        assert_eq!(crate::Gsm7ArrayString::<7>::CAPACITY, 8);
+        s.push_str("hell").unwrap();
+        s.push('€').unwrap();
+        assert_eq!(s.push_str("ab√"), Err(Gsm7Error::Unencodable('√')));
+        assert_eq!(s.push_str("abc"), Err(Gsm7Error::BufferTooSmall));
+        s.push('!').unwrap();
+        assert_eq!(s.push('{'), Err(Gsm7Error::BufferTooSmall));
+        assert_eq!(s.to_string(), "hell€!");
+        assert_eq!(s.as_bytes(), &crate::encode("hell€!").unwrap()[..]);
+        let mut out = [0; 16];
+        let len = s.decode_into(&mut out).unwrap();
+        assert_eq!(&out[..len], "hell€!".as_bytes());
+        assert_eq!(s.decode_into(&mut out[..4]), Err(Gsm7Error::BufferTooSmall));
+        s.clear();
+        assert_eq!((s.septet_len(), s.as_bytes()), (0, &[][..]));
+    }
+
+    #[test]
+    fn gsm7_string_behaves_like_a_string() {
+        use std::collections::{BTreeSet, HashSet};
+        let a = "abc".parse::<Gsm7String>().unwrap();
+        let b = "abd".parse::<Gsm7String>().unwrap();
+        assert!(a < b && a == "abc" && a.starts_with("ab"));
+        assert_eq!(format!("{:>5}", a), "  abc");
+        let set: HashSet<_> = vec![a.clone(), b.clone()].into_iter().collect();
+        assert!(set.contains("abd"));
+        let sorted: Vec<_> = vec![b, a].into_iter().collect::<BTreeSet<_>>().into_iter().collect();
+        assert_eq!(sorted, ["abc", "abd"]);
+    }
+
+    #[test]
+    fn public_tables_agree_with_the_codec() {
+        use crate::{GSM7_CHARSET, GSM7_EXTENSION, GSM7_EXTENSION_CHARS};
+        for (septet, &c) in GSM7_CHARSET.iter().enumerate().filter(|&(septet, _)| septet != 0x1B) {
+            assert_eq!(crate::unpack_septets(&crate::encode(&c.to_string()).unwrap())[0], septet as u8);
+        }
+        for &(c, septet) in &GSM7_EXTENSION_CHARS {
+            assert_eq!(GSM7_EXTENSION[septet as usize], Some(c));
+            assert_eq!(crate::decode(&crate::pack_septets(&[0x1B, septet])).unwrap(), c.to_string());
+        }
+        assert_eq!(GSM7_EXTENSION.iter().flatten().count(), GSM7_EXTENSION_CHARS.len());
+    }
+
+    #[test]
+    fn supported_chars_cover_both_tables() {
+        use crate::{supported_chars, Table};
+        let chars: Vec<_> = supported_chars().collect();
+        assert_eq!(chars.len(), 137);
+        assert_eq!((chars[0].char, chars[0].septet, chars[0].septets()), ('@', 0x00, 1));
+        let euro = chars.iter().find(|c| c.char == '€').unwrap();
+        assert_eq!((euro.septet, euro.table, euro.septets()), (0x65, Table::Extension, 2));
+        for c in &chars {
+            assert_eq!(crate::segment::costs(&c.char.to_string()).next().unwrap().1, Some(c.septets() as u8));
+        }
+    }
+
+    #[test]
+    fn char_predicates_match_lookup() {
+        use crate::{is_basic_char, is_extension_char, is_gsm7_char};
+        for c in (0..0x3000).filter_map(char::from_u32) {
+            let septets = crate::segment::costs(&c.to_string()).next().unwrap().1;
+            assert_eq!(is_basic_char(c), septets == Some(1), "{:?}", c);
+            assert_eq!(is_extension_char(c), septets == Some(2), "{:?}", c);
+            assert_eq!(is_gsm7_char(c), septets.is_some(), "{:?}", c);
+        }
+        assert!(!is_gsm7_char('\x1B'));
+    }
+
+    #[test]
+    fn nul_terminated_fields_stop_at_0x00() {
+        let field = crate::pack_septets(&[0x41, 0x1B, 0x65, 0x00, 0x42]);
+        assert_eq!(Chars::new(&field).nul_terminated().collect::<Result<String, _>>().unwrap(), "A€");
+        assert_eq!(Chars::new(&field).nul_terminated().rev().collect::<Result<String, _>>().unwrap(), "€A");
+        let mut reader = Gsm7Reader::new(&field[..]).nul_terminated();
+        assert_eq!(reader.by_ref().collect::<io::Result<String>>().unwrap(), "A€");
+        assert!(reader.next().is_none());
+        let reader = Gsm7BufReader::new(&field[..]).nul_terminated();
+        assert_eq!(reader.collect::<io::Result<String>>().unwrap(), "A€");
+        assert_eq!(Gsm7Reader::new(&[0x00, 0x41][..]).nul_terminated().count(), 0);
+    }
+
+    #[test]
+    fn line_endings_are_normalized() {
+        use crate::{normalize_line_endings, LineEnding};
+        use std::borrow::Cow;
+        let text = "a\r\nb\nc\rd";
+        assert_eq!(normalize_line_endings(text, LineEnding::Lf), "a\nb\nc\nd");
+        assert_eq!(normalize_line_endings(text, LineEnding::Cr), "a\rb\rc\rd");
+        assert_eq!(normalize_line_endings(text, LineEnding::CrLf), "a\r\nb\r\nc\r\nd");
+        assert!(matches!(normalize_line_endings(text, LineEnding::Keep), Cow::Borrowed(_)));
+        assert!(matches!(normalize_line_endings("a\nb", LineEnding::Lf), Cow::Borrowed(_)));
+
+        let mut writer = Gsm7Writer::new(Vec::new()).line_endings(LineEnding::Cr);
+        writer.write_str("one\r").unwrap();
+        writer.write_str("\ntwo\n").unwrap();
+        let packed = writer.into_writer().unwrap();
+        assert_eq!(packed, crate::encode("one\rtwo\r").unwrap());
+    }
+
+    #[test]
+    fn control_chars_can_be_replaced_or_rejected() {
+        use crate::ControlPolicy;
+        let packed = crate::encode("a\r\nb\x0C").unwrap();
+        let replaced = Chars::new(&packed).control_chars(ControlPolicy::Replace(' '), "\n");
+        assert_eq!(replaced.clone().collect::<Result<String, _>>().unwrap(), "a \nb ");
+        assert_eq!(replaced.rev().collect::<Result<String, _>>().unwrap(), " b\n a");
+        let reader = Gsm7Reader::new(&packed[..]).control_chars(ControlPolicy::Reject, "\r\n");
+        let e = reader.collect::<io::Result<String>>().unwrap_err();
+        let e = e.get_ref().unwrap().downcast_ref::<crate::DecodeError>().unwrap();
+        assert_eq!((e.error, e.septet), (Gsm7Error::ControlChar('\x0C'), 4));
+        let reader = Gsm7BufReader::new(&packed[..]).control_chars(ControlPolicy::Reject, "");
+        assert_eq!(reader.collect::<io::Result<String>>().unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn alphabets_for_languages() {
+        use crate::{Alphabet, NationalLanguage, Padding};
+        let turkish = Alphabet::for_language("tr-TR");
+        let tables = (Some(NationalLanguage::Turkish), Some(NationalLanguage::Turkish));
+        assert_eq!((turkish.locking_shift, turkish.single_shift), tables);
+        assert_eq!(Alphabet::for_language("es_ES").locking_shift, None);
+        assert_eq!(Alphabet::for_language("ES").single_shift, Some(NationalLanguage::Spanish));
+        assert_eq!(Alphabet::for_language("pt").locking_shift, Some(NationalLanguage::Portuguese));
+        assert_eq!(Alphabet::for_language("en-GB"), Alphabet::DEFAULT);
+        assert_eq!(Alphabet::all().count(), 12);
+
+        let text = "Işık ğüzel, €5";
+        assert_eq!(Alphabet::DEFAULT.septets('ı'), None);
+        assert_eq!(turkish.septets('ı'), Some(1));
+        assert_eq!((turkish.septets('İ'), turkish.septets('{'), turkish.septets('è')), (Some(1), Some(2), None));
+        let packed = turkish.encode(text, Padding::Zero).unwrap();
+        assert_eq!(crate::unpack_septets(&packed)[..3], [0x49, 0x1D, 0x07]);
+        assert_eq!(turkish.decode(&packed, Some(14)).unwrap(), text);
+        assert_eq!(turkish.header_elements().collect::<Vec<_>>(), [[0x25, 1, 1], [0x24, 1, 1]]);
+        assert_eq!(Alphabet::for_language("es").header_elements().collect::<Vec<_>>(), [[0x24, 1, 2]]);
+
+        let spanish = Alphabet::for_language("es");
+        assert_eq!(spanish.decode(&spanish.encode("¿Qué tal, Tomás?", Padding::Cr).unwrap(), Some(17)).unwrap(), "¿Qué tal, Tomás?");
+        assert_eq!(Alphabet::DEFAULT.encode("{x}", Padding::Zero).unwrap(), crate::encode("{x}").unwrap());
+        // Seven spare bits are filled with CR only when asked.
+        assert_eq!(Alphabet::DEFAULT.encode("1234567", Padding::Zero).unwrap()[6], 0x00);
+        assert_eq!(Alphabet::DEFAULT.encode("1234567", Padding::Cr).unwrap()[6], 0x1A);
+        assert_eq!(turkish.decode(&[0x1B], Some(1)), Err(Gsm7Error::TruncatedEscape));
+    }
+
+    #[test]
+    fn coverage_counts_per_alphabet() {
+        use crate::{coverage, Alphabet};
+        let report = coverage("Dağ ğ {x}");
+        assert_eq!(report.len(), Alphabet::all().count());
+        assert_eq!((report[0].alphabet, report[0].uncovered, report[0].septets), (Alphabet::DEFAULT, 2, 9));
+        let turkish = report.iter().find(|c| c.alphabet == Alphabet::for_language("tr")).unwrap();
+        assert_eq!((turkish.uncovered, turkish.septets), (0, 11));
+        let best = report.iter().filter(|c| c.uncovered == 0).min_by_key(|c| c.septets).unwrap();
+        assert_eq!((best.alphabet.locking_shift, best.septets), (turkish.alphabet.locking_shift, 11));
+    }
+
+    #[test]
+    fn text_shares_a_bit_stream() -> io::Result<()> {
+        use bitstream_io::{BitRead, BitReader, BitWrite, BitWriter, LittleEndian};
+
+        use crate::{Gsm7BitReader, Gsm7BitWriter};
 
+        let mut bits = BitWriter::endian(Vec::new(), LittleEndian);
+        bits.write(3, 0b101u8)?;
+        let mut writer = Gsm7BitWriter::with_bit_writer(bits);
+        writer.write_str("Hi{")?;
+        let mut bits = writer.into_bit_writer();
+        bits.write(5, 0b10011u8)?;
+        bits.byte_align()?;
+        let out = bits.into_writer();
+
+        let mut bits = BitReader::endian(&out[..], LittleEndian);
+        assert_eq!(bits.read::<u8>(3)?, 0b101);
+        let mut reader = Gsm7BitReader::with_bit_reader(bits).udl(4);
+        let text = reader.by_ref().collect::<io::Result<String>>()?;
+        assert_eq!(text, "Hi{");
+        let mut bits = reader.into_bit_reader();
+        assert_eq!(bits.read::<u8>(5)?, 0b10011);
         Ok(())
     }
 
     #[test]
-    fn it_works_correctly() -> io::Result<()> {
-        let v: Vec<_> = vec![84, 58, 157, 14].into_iter().collect();
-        let reader = Gsm7Reader::new(io::Cursor::new(&v));
-        let s = reader.collect::<io::Result<String>>()?;
-        assert_eq!(&s, "Tttt");
+    fn writer_writes_fields_of_concrete_widths() {
+        let mut writer = Gsm7Writer::new(Vec::new());
+        writer.write_u32_bits(20, 0xABCDE).unwrap();
+        writer.write_u8_bits(4, 0x7).unwrap();
+        assert_eq!(writer.into_writer().unwrap(), [0xDE, 0xBC, 0x7A]);
 
-        let v = vec![0xD4, 0xF2, 0x9C, 0x0E];
-        let reader = Gsm7Reader::new(io::Cursor::new(&v));
-        let s: String = reader.collect::<io::Result<String>>()?;
-        assert_eq!(&s, "Test");
+        let mut writer = Gsm7Writer::new(Vec::new());
+        assert_eq!(writer.write_u8_bits(9, 0).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(writer.write_u8_bits(3, 0x8).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn big_endian_packing_round_trips() -> io::Result<()> {
+        use bitstream_io::{BigEndian, BitWrite, BitWriter};
+
+        use crate::Gsm7BitWriter;
+
+        let mut writer = Gsm7Writer::endian(Vec::new(), BigEndian);
+        writer.write_str("Hi")?;
+        assert_eq!(writer.into_writer()?, [0x91, 0xA4]);
+
+        // Long enough to take the ASCII run path, with an escape to leave it.
+        let text = "Hello, big-endian world {ok}";
+        let mut writer = Gsm7Writer::endian(Vec::new(), BigEndian);
+        writer.write_str(text)?;
+        let packed = writer.into_writer()?;
+        let mut writer = Gsm7Writer::endian(Vec::new(), BigEndian);
+        text.chars().try_for_each(|c| writer.write_char(c).map(drop))?;
+        assert_eq!(writer.into_writer()?, packed);
+        assert_ne!(packed, crate::encode(text).unwrap());
+        let decoded = Gsm7Reader::endian(&packed[..], BigEndian).collect::<io::Result<String>>()?;
+        assert_eq!(decoded, text);
+
+        // A big-endian bit writer of the caller's, partway through a byte.
+        let mut bits = BitWriter::endian(Vec::new(), BigEndian);
+        bits.write(4, 0xAu8)?;
+        let mut writer = Gsm7BitWriter::with_bit_writer(bits);
+        writer.write_str(text)?;
+        let mut bits = writer.into_bit_writer();
+        bits.byte_align()?;
+        let mut expected = BitWriter::endian(Vec::new(), BigEndian);
+        expected.write(4, 0xAu8)?;
+        for septet in crate::unpack_septets(&crate::encode(text).unwrap()) {
+            expected.write(7, septet)?;
+        }
+        expected.byte_align()?;
+        assert_eq!(bits.into_writer(), expected.into_writer());
+        Ok(())
+    }
+
+    #[test]
+    fn reader_from_slice_knows_its_length() {
+        let packed = crate::encode("Hello").unwrap();
+        let reader = Gsm7Reader::from_slice(&packed);
+        assert_eq!(reader.size_hint(), (3, Some(5)));
+        assert_eq!(reader.collect::<io::Result<String>>().unwrap(), "Hello");
+        let reader: Gsm7Reader<&[u8]> = packed[..].into();
+        assert_eq!(reader.count(), 5);
+    }
+
+    #[test]
+    fn decode_all_collects_the_rest() {
+        let packed = crate::encode("Hello {world}!").unwrap();
+        assert_eq!(Gsm7Reader::from_slice(&packed).decode_all().unwrap(), "Hello {world}!");
+        assert_eq!(Gsm7BufReader::new(&packed[..]).decode_all().unwrap(), "Hello {world}!");
+        let mut reader = Gsm7Reader::with_udl(&packed[..], 5);
+        reader.next();
+        assert_eq!(reader.decode_all().unwrap(), "ello");
+        let e = Gsm7Reader::with_udl(&packed[..], 20).decode_all().unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn io_errors_carry_the_gsm7_error() {
+        let mut writer = Gsm7Writer::new(Vec::new());
+        let e = writer.write_str("ok √").unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(e.get_ref().and_then(|e| e.downcast_ref()), Some(&Gsm7Error::Unencodable('√')));
+        let e = io::Error::from(Gsm7Error::UnexpectedEnd);
+        assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+        assert_eq!(e.into_inner().unwrap().downcast::<Gsm7Error>().unwrap(), Box::new(Gsm7Error::UnexpectedEnd));
+    }
+
+    #[test]
+    fn writer_counts_septets_written() -> io::Result<()> {
+        let mut writer = Gsm7Writer::new(Vec::new()).line_endings(crate::LineEnding::CrLf);
+        assert_eq!(writer.write_char('a')?, 1);
+        assert_eq!(writer.write_char('€')?, 2);
+        assert_eq!(writer.write_str("Hello, {world}\n")?, 18);
+        assert_eq!(writer.write_str("")?, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn can_fit_checks_the_budget() -> io::Result<()> {
+        let mut writer = Gsm7Writer::with_capacity_septets(Vec::new(), 3);
+        writer.write_str("ab")?;
+        assert!(writer.can_fit('c'));
+        assert!(!writer.can_fit('€'));
+        assert!(!writer.can_fit('√'));
+        writer.write_char('c')?;
+        assert!(!writer.can_fit('d'));
+        assert!(Gsm7Writer::new(Vec::new()).can_fit('€'));
+        Ok(())
+    }
+
+    #[test]
+    fn segmented_writer_rolls_over_parts() {
+        use std::cell::RefCell;
+
+        use crate::pdu::{Error, SegmentedWriter};
+        use crate::{DefaultAlphabet, UserDataEncoder};
+
+        let parts = RefCell::new(Vec::new());
+        let mut writer = SegmentedWriter::new(9, 2, |part| parts.borrow_mut().push(part));
+        assert_eq!(writer.write_str(&"a".repeat(159)), Ok(159));
+        assert_eq!(writer.write_char('b'), Ok(1));
+        assert_eq!(writer.parts(), 1);
+        // The first part is held back while the text still fits a single SMS.
+        assert!(parts.borrow().is_empty());
+        writer.clone().finish().unwrap();
+        assert_eq!(parts.take(), [DefaultAlphabet.encode_user_data(&("a".repeat(159) + "b"), None).unwrap()]);
+
+        assert_eq!(writer.write_str("{}"), Ok(4));
+        assert_eq!(writer.parts(), 2);
+        assert_eq!(parts.borrow().len(), 1);
+        writer.finish().unwrap();
+        let text = "a".repeat(159) + "b{}";
+        let expected = crate::split_for_sms(&text).unwrap().into_iter().zip(1..).map(|(part, seq)| {
+            DefaultAlphabet.encode_user_data(part, Some(&[0x00, 3, 9, 2, seq])).unwrap()
+        });
+        assert_eq!(parts.borrow()[..], expected.collect::<Vec<_>>()[..]);
+        assert_eq!(parts.borrow()[0].udl, 160);
+
+        // Each part is handed out as soon as the next starts, and unneeded ones come empty.
+        let parts = RefCell::new(Vec::new());
+        let mut writer = SegmentedWriter::new(1, 4, |part| parts.borrow_mut().push(part));
+        writer.write_str(&"a".repeat(153 * 2 + 1)).unwrap();
+        assert_eq!(parts.borrow().len(), 2);
+        writer.finish().unwrap();
+        let udls = parts.borrow().iter().map(|part| part.udl).collect::<Vec<_>>();
+        assert_eq!(udls, [160, 160, 8, 7]);
+
+        let mut writer = SegmentedWriter::new(0, 1, drop);
+        assert_eq!(writer.write_str("√"), Err(Error::Gsm7(Gsm7Error::Unencodable('√'))));
+        assert_eq!(writer.write_str(&"a".repeat(160)), Ok(160));
+        assert_eq!(writer.write_char('a'), Err(Error::TooManySegments));
+        let mut writer = SegmentedWriter::new(0, 255, drop);
+        assert_eq!(writer.write_str(&"a".repeat(153 * 256)), Err(Error::TooManySegments));
+    }
+
+    #[test]
+    fn udl_prefix_is_filled_in() -> io::Result<()> {
+        let mut buf = io::Cursor::new(vec![0xAA]);
+        buf.set_position(1);
+        let mut writer = Gsm7Writer::with_udl_prefix(buf)?;
+        writer.write_str("Hello {}")?;
+        assert_eq!(writer.finish(crate::Padding::Zero)?, 9);
+        writer.start_new_message()?;
+        writer.write_str("!")?;
+        assert_eq!(writer.finish(crate::Padding::Zero)?, 1);
+        writer.start_new_message()?;
+        writer.write_str("Bye")?;
+        let buf = writer.into_writer()?.into_inner();
+        let mut expected = vec![0xAA, 10];
+        expected.extend(crate::encode("Hello {}").unwrap());
+        expected.extend([1, b'!', 3]);
+        expected.extend(crate::encode("Bye").unwrap());
+        assert_eq!(buf, expected);
+
+        // Each message reads back with its own UDL.
+        assert_eq!(Gsm7Reader::with_udl_prefix(&buf[11..])?.decode_all()?, "!");
+        assert_eq!(Gsm7Reader::with_udl_prefix(&buf[13..])?.decode_all()?, "Bye");
+
+        let writer = Gsm7Writer::with_udl_prefix(io::Cursor::new(Vec::new()))?;
+        assert_eq!(writer.into_writer()?.into_inner(), [0]);
+        Ok(())
+    }
 
+    #[test]
+    fn reader_takes_the_udl_from_a_prefix() -> io::Result<()> {
+        let mut writer = Gsm7Writer::with_udl_prefix(io::Cursor::new(Vec::new()))?;
+        writer.write_str("Hi there")?;
+        let buf = writer.into_writer()?.into_inner();
+        assert_eq!(Gsm7Reader::with_udl_prefix(&buf[..])?.decode_all()?, "Hi there");
+        let mut short = buf.clone();
+        short[0] = 7;
+        assert_eq!(Gsm7Reader::with_udl_prefix(&short[..])?.decode_all()?, "Hi ther");
+        let e = Gsm7Reader::with_udl_prefix(&[][..]).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
         Ok(())
     }
+
+    #[test]
+    fn header_arithmetic_matches_the_budgets() {
+        use crate::{fill_bits, header_septets, text_septets, udl, user_data_octets, Budget};
+
+        assert_eq!((header_septets(0), fill_bits(0), udl(0, 160)), (0, 0, 160));
+        // An 8-bit reference concatenation header: UDHL, IEI, length and three fields.
+        assert_eq!((header_septets(6), fill_bits(6)), (7, 1));
+        assert_eq!(udl(6, Budget::Concat8.septets()), 160);
+        assert_eq!((header_septets(7), fill_bits(7), udl(7, Budget::Concat16.septets())), (8, 0, 160));
+        assert_eq!(text_septets(6, 160), Some(153));
+        assert_eq!(text_septets(6, 6), None);
+        assert_eq!((user_data_octets(160), user_data_octets(8), user_data_octets(9)), (140, 7, 8));
+        for octets in 0..140 {
+            assert_eq!(header_septets(octets) * 7, octets * 8 + fill_bits(octets) as usize);
+            assert!(fill_bits(octets) < 7);
+        }
+    }
+
+    #[test]
+    fn planner_picks_the_fewest_parts() {
+        use crate::{plan_message, Alphabet, NationalLanguage, PartEncoding};
+
+        let encodings = |s: &str| plan_message(s).iter().map(|part| part.encoding).collect::<Vec<_>>();
+        let default = PartEncoding::Gsm7(Alphabet::DEFAULT);
+        assert_eq!(encodings("Hello"), [default]);
+        assert_eq!(encodings(""), [default]);
+        assert_eq!(encodings("√"), [PartEncoding::Ucs2]);
+        // The Turkish single shift table avoids UCS-2 for ı and ş.
+        let alphabet = Alphabet { locking_shift: None, single_shift: Some(NationalLanguage::Turkish) };
+        assert_eq!(encodings("Kışın"), [PartEncoding::Gsm7(alphabet)]);
+        // A long text with one emoji at the end costs three UCS-2 parts, or two when mixed.
+        let text = "a".repeat(150) + "😀";
+        let plan = plan_message(&text);
+        assert_eq!(plan.iter().map(|part| part.encoding).collect::<Vec<_>>(), [default, PartEncoding::Ucs2]);
+        assert_eq!(plan[0].text.len() + plan[1].text.len(), text.len());
+        assert_eq!(plan[1].text, "😀");
+    }
+
+    #[test]
+    fn non_gsm7_chars_are_found() {
+        use crate::{first_non_gsm7_char, requires_ucs2};
+
+        assert!(!requires_ucs2("Hello {€}"));
+        assert!(requires_ucs2("Hello 👋"));
+        assert_eq!(first_non_gsm7_char("Hello {€}"), None);
+        let c = first_non_gsm7_char("Hi 😀 ✓").unwrap();
+        assert_eq!((c.offset, c.char, c.is_astral(), c.is_emoji()), (3, '😀', true, true));
+        let c = first_non_gsm7_char("Zoë ✓").unwrap();
+        assert_eq!((c.offset, c.char, c.is_astral(), c.is_emoji()), (2, 'ë', false, false));
+        assert!(first_non_gsm7_char("✓").unwrap().is_emoji());
+        let c = first_non_gsm7_char("𝐀").unwrap();
+        assert!(c.is_astral() && !c.is_emoji());
+    }
+
+    #[test]
+    fn homoglyphs_become_gsm7() {
+        use std::borrow::Cow;
+
+        use crate::normalize_homoglyphs;
+
+        assert_eq!(normalize_homoglyphs("ＨＥＬＬＯ　２０２４"), "HELLO 2024");
+        assert_eq!(normalize_homoglyphs("5 \u{2212} 3\u{00A0}= 2"), "5 - 3 = 2");
+        // Cyrillic А, о and р among Latin letters.
+        assert_eq!(normalize_homoglyphs("\u{0410}ll g\u{043E}\u{043E}d, \u{0440}al"), "All good, pal");
+        assert!(crate::encode(&normalize_homoglyphs("ｐａｙ ＄５")).is_ok());
+        // Full-width ` has no GSM 7-bit character to become, and Cyrillic Д looks like nothing Latin.
+        assert_eq!(normalize_homoglyphs("\u{FF40}Д"), "\u{FF40}Д");
+        assert!(matches!(normalize_homoglyphs("plain"), Cow::Borrowed("plain")));
+    }
+
+    #[test]
+    fn writer_replaces_unencodable_chars() -> io::Result<()> {
+        use std::borrow::Cow;
+
+        let replace = |c: char| match c {
+            '½' => Some(Cow::Borrowed("1/2")),
+            '√' => Some(Cow::Owned(String::from("{sqrt}"))),
+            '😀' => Some(Cow::Borrowed("")),
+            _ => None,
+        };
+        let mut writer = Gsm7Writer::new(Vec::new()).replace_unencodable(replace);
+        assert_eq!(writer.write_char('½')?, 3);
+        assert_eq!(writer.write_str(" √2 😀!")?, 12);
+        let e = writer.write_char('✓').unwrap_err();
+        assert_eq!(e.get_ref().and_then(|e| e.downcast_ref()), Some(&Gsm7Error::Unencodable('✓')));
+        assert_eq!(writer.into_writer()?, crate::encode("1/2 {sqrt}2 !").unwrap());
+
+        let mut writer = Gsm7Writer::with_capacity_septets(Vec::new(), 2).replace_unencodable(|_| Some("abc".into()));
+        let e = writer.write_char('√').unwrap_err();
+        assert_eq!(e.get_ref().and_then(|e| e.downcast_ref()), Some(&Gsm7Error::WouldOverflow));
+        Ok(())
+    }
+
+    #[test]
+    fn advice_explains_the_costs() {
+        use crate::{advise, Alphabet, PartEncoding};
+
+        let advice = advise("Año {x} ı 😀");
+        assert_eq!(advice.chars.len(), 11);
+        assert_eq!(advice.chars[1].septets.len(), Alphabet::all().count());
+        let costly = advice.costly_chars().map(|cost| (cost.char, cost.septets[0], cost.forces_ucs2()));
+        let costly = costly.collect::<Vec<_>>();
+        assert_eq!(costly, [('{', Some(2), false), ('}', Some(2), false), ('ı', None, false), ('😀', None, true)]);
+        assert_eq!(advice.chars.last().unwrap().ucs2_units, 2);
+        assert_eq!(advice.coverage[0].uncovered, 2);
+        assert!(advice.plan.iter().all(|part| part.encoding == PartEncoding::Ucs2));
+    }
+
+    #[test]
+    fn log_decoder_reassembles_wrapped_pdus() {
+        use crate::pdu::decode_log;
+
+        let log = [
+            "AT+CMGR=1",
+            "+CMGR: 0,,30",
+            "07911326040000F0040B911346610089F600002080629173140",
+            "80CC8F71D14969741F977FD07",
+            "OK",
+            "+CMT: ,30",
+            "07911326040000F0040B911346610089F60000208062917314080CC8F71D14969741F977FD07",
+            "07911326040000F0040B911346610089F60000208062917314080CC8F71D14969741F977FD07",
+        ];
+        let messages: Vec<_> = decode_log(log).collect::<Result<_, _>>().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert!(messages.iter().all(|message| *message == messages[0]));
+        assert_eq!(messages[0].originator, "+31641600986");
+        assert_eq!(messages[0].user_data.text, "How are you?");
+        assert_eq!(messages[0].timestamp, [0x20, 0x80, 0x62, 0x91, 0x73, 0x14, 0x08]);
+    }
+
+    #[test]
+    fn urcs_dispatch_to_their_parsers() {
+        use crate::urc::{parse_urc, parse_ussd, CellBroadcast, Urc, Ussd, UssdFormat};
+
+        let deliver = "+CMT: ,30\r\n07911326040000F0040B911346610089F60000208062917314080CC8F71D14969741F977FD07";
+        match parse_urc(deliver, UssdFormat::Hex).unwrap() {
+            Urc::Deliver(message) => assert_eq!(message.user_data.text, "How are you?"),
+            urc => panic!("{:?}", urc),
+        }
+
+        let report = "+CDS: 25\r\n0006070B911346610089F6208062917314082080629173540800";
+        match parse_urc(report, UssdFormat::Hex).unwrap() {
+            Urc::StatusReport(report) => {
+                assert_eq!((report.message_reference, report.status), (7, 0));
+                assert_eq!(report.recipient, "+31641600986");
+                assert_eq!(report.discharge_time[5], 0x54);
+            },
+            urc => panic!("{:?}", urc),
+        }
+
+        let ussd = format!("+CUSD: 1,\"{}\",15", crate::encode_to_hex("Balance: 5.00").unwrap());
+        let expected = Ussd { status: 1, text: Some("Balance: 5.00".into()), dcs: Some(15) };
+        assert_eq!(parse_urc(&ussd, UssdFormat::Hex).unwrap(), Urc::Ussd(expected));
+        let ended = Ussd { status: 2, text: None, dcs: None };
+        assert_eq!(parse_urc("+CUSD: 2", UssdFormat::Hex).unwrap(), Urc::Ussd(ended));
+        // Text that happens to be hex is left alone unless the modem gives hex.
+        let text = Ussd { status: 0, text: Some("1000".into()), dcs: Some(15) };
+        assert_eq!(parse_ussd(" 0,\"1000\",15", UssdFormat::Text).unwrap(), text);
+        let invalid_hex = Err(crate::pdu::Error::Gsm7(Gsm7Error::InvalidHex));
+        assert_eq!(parse_ussd(" 0,\"Balance\",15", UssdFormat::Hex), invalid_hex);
+
+        let mut page = vec![0x40, 0x10, 0x11, 0x12, 0x0F, 0x12];
+        page.extend(crate::encode(&format!("{:\r<93}", "Storm warning")).unwrap());
+        let cbm = format!("+CBM: 88\n{}", crate::hex::to_hex(&page));
+        let expected = CellBroadcast {
+            serial: 0x4010,
+            message_id: 0x1112,
+            dcs: 0x0F,
+            page: 1,
+            pages: 2,
+            text: "Storm warning".into(),
+        };
+        assert_eq!(parse_urc(&cbm, UssdFormat::Hex).unwrap(), Urc::CellBroadcast(expected));
+        assert_eq!(parse_urc("+CMTI: \"SM\",1", UssdFormat::Hex), Err(crate::pdu::Error::InvalidResultCode));
+    }
+
+    #[test]
+    fn cell_broadcasts_reject_compression_and_drop_headers() {
+        use crate::urc::parse_cell_broadcast;
+        use crate::{DefaultAlphabet, UserDataEncoder};
+
+        let page = |dcs: u8, content: &[u8]| {
+            let mut page = vec![0x40, 0x10, 0x11, 0x12, dcs, 0x11];
+            page.extend(content);
+            page
+        };
+        let text = crate::encode("Flood alert").unwrap();
+        let unsupported = Err(crate::pdu::Error::Gsm7(Gsm7Error::UnsupportedDcs(0x60)));
+        assert_eq!(parse_cell_broadcast(&page(0x60, &text)), unsupported);
+
+        let ud = DefaultAlphabet.encode_user_data("Flood alert", Some(&[0x00, 0x03, 0x01, 0x02, 0x01])).unwrap();
+        assert_eq!(parse_cell_broadcast(&page(0x90, &ud.user_data)).unwrap().text, "Flood alert");
+        let mut ucs2 = vec![0x02, 0x70, 0x00];
+        ucs2.extend("Flood".encode_utf16().flat_map(u16::to_be_bytes));
+        assert_eq!(parse_cell_broadcast(&page(0x98, &ucs2)).unwrap().text, "Flood");
+    }
+
+    #[test]
+    fn sms_records_parse_received_and_unsent_messages() {
+        use crate::pdu::{parse_sms_record, submit_pdus, SmsRecord};
+
+        let record = |status: u8, pdu: &[u8]| {
+            let mut record = vec![status];
+            record.extend(pdu);
+            record.resize(176, 0xFF);
+            record
+        };
+        let deliver = crate::hex::from_hex(
+            "07911326040000F0040B911346610089F60000208062917314080CC8F71D14969741F977FD07",
+        )
+        .unwrap();
+        match parse_sms_record(&record(0x03, &deliver)).unwrap() {
+            SmsRecord::Received { read, message } => {
+                assert!(!read);
+                assert_eq!(message.user_data.text, "How are you?");
+            },
+            stored => panic!("{:?}", stored),
+        }
+
+        let submit = submit_pdus("+447700900123", "See you at 8", 0).unwrap().remove(0);
+        let mut pdu = vec![0x00];
+        pdu.extend(&submit);
+        assert_eq!(parse_sms_record(&record(0x07, &pdu)).unwrap(), SmsRecord::Submit { sent: false, tpdu: submit });
+        assert_eq!(parse_sms_record(&record(0x00, &[])).unwrap(), SmsRecord::Free);
+    }
+
+    #[test]
+    fn log_decoder_yields_messages_before_the_input_ends() {
+        use crate::pdu::decode_log;
+
+        let pdu = "07911326040000F0040B911346610089F60000208062917314080CC8F71D14969741F977FD07";
+        // A live log, whose next line never comes.
+        let next_line = || -> Option<&str> { panic!("read past the PDU") };
+        let lines = vec!["+CMT: ,30", pdu].into_iter().chain(core::iter::from_fn(next_line));
+        let mut messages = decode_log(lines);
+        assert_eq!(messages.next().unwrap().unwrap().user_data.text, "How are you?");
+    }
+
+    #[test]
+    fn cloned_reader_decodes_the_same_rest() {
+        let packed = crate::encode("Clone me {here}").unwrap();
+        let mut reader = Gsm7Reader::from_slice(&packed);
+        assert_eq!(reader.by_ref().take(6).collect::<io::Result<String>>().unwrap(), "Clone ");
+        let copy = reader.clone();
+        assert_eq!(copy.decode_all().unwrap(), "me {here}");
+        assert_eq!(reader.decode_all().unwrap(), "me {here}");
+    }
+
+    #[test]
+    fn terminated_reader_hints_no_lower_bound() {
+        let packed = [0x00, 0x00, 0x00];
+        let mut reader = Gsm7Reader::from_slice(&packed).nul_terminated();
+        assert_eq!(reader.size_hint(), (0, Some(3)));
+        assert!(reader.next().is_none());
+        assert_eq!(reader.size_hint(), (0, Some(0)));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn pdu_structs_round_trip_through_json() {
+        use crate::pdu::{parse_deliver_with_smsc, Deliver};
+        use crate::{DefaultAlphabet, EncodedUserData, UserDataEncoder};
+
+        let pdu = crate::hex::from_hex(
+            "07911326040000F0040B911346610089F60000208062917314080CC8F71D14969741F977FD07",
+        )
+        .unwrap();
+        let message = parse_deliver_with_smsc(&pdu).unwrap();
+        let json = serde_json::to_string(&message).unwrap();
+        assert!(json.contains(r#""originator":"+31641600986""#));
+        assert_eq!(serde_json::from_str::<Deliver>(&json).unwrap(), message);
+
+        let ud = DefaultAlphabet.encode_user_data("Hi", Some(&[0x00, 0x03, 0x01, 0x02, 0x01])).unwrap();
+        let json = serde_json::to_string(&ud).unwrap();
+        assert_eq!(serde_json::from_str::<EncodedUserData>(&json).unwrap(), ud);
+    }
+
+    #[test]
+    fn segment_count_respects_escape_pairs() {
+        assert_eq!(crate::segment_count(""), Ok(1));
+        assert_eq!(crate::segment_count(&"a".repeat(160)), Ok(1));
+        assert_eq!(crate::segment_count(&"a".repeat(161)), Ok(2));
+        assert_eq!(crate::segment_count(&"€".repeat(80)), Ok(1));
+        // 76 escape pairs fill 152 septets, so the 77th can't be split and starts a new part.
+        assert_eq!(crate::segment_count(&"€".repeat(81)), Ok(2));
+        assert_eq!(crate::segment_count(&"€".repeat(153)), Ok(3));
+        assert_eq!(crate::segment_count("ú"), Err(Gsm7Error::Unencodable('ú')));
+    }
+
+    #[cfg(feature = "ffi")]
+    #[test]
+    fn ffi_functions_report_status() {
+        use crate::ffi::{gsm7_decode, gsm7_encode, gsm7_segment_count, Gsm7Status, GSM7_NO_UDL};
+
+        unsafe {
+            let text = "{Hello}";
+            let (mut packed, mut len) = ([0u8; 16], 0);
+            let status = gsm7_encode(text.as_ptr(), text.len(), packed.as_mut_ptr(), packed.len(), &mut len);
+            assert_eq!(status, Gsm7Status::Ok);
+            assert_eq!(&packed[..len], &crate::encode(text).unwrap()[..]);
+            let status = gsm7_encode(text.as_ptr(), text.len(), packed.as_mut_ptr(), 4, &mut len);
+            assert_eq!(status, Gsm7Status::BufferTooSmall);
+            let status = gsm7_encode(core::ptr::null(), 1, packed.as_mut_ptr(), packed.len(), &mut len);
+            assert_eq!(status, Gsm7Status::NullPointer);
+            let status = gsm7_encode(text.as_ptr(), text.len(), packed.as_mut_ptr(), packed.len(), &mut len);
+            assert_eq!(status, Gsm7Status::Ok);
+
+            let (mut out, mut out_len) = ([0u8; 16], 0);
+            let status = gsm7_decode(packed.as_ptr(), len, 9, out.as_mut_ptr(), out.len(), &mut out_len);
+            assert_eq!(status, Gsm7Status::Ok);
+            assert_eq!(&out[..out_len], text.as_bytes());
+            let status = gsm7_decode(packed.as_ptr(), len, GSM7_NO_UDL, out.as_mut_ptr(), 4, &mut out_len);
+            assert_eq!(status, Gsm7Status::BufferTooSmall);
+            let status = gsm7_decode(packed.as_ptr(), len, 9, core::ptr::null_mut(), out.len(), &mut out_len);
+            assert_eq!(status, Gsm7Status::NullPointer);
+
+            let mut count = 0;
+            assert_eq!(gsm7_segment_count(b"\xff".as_ptr(), 1, &mut count), Gsm7Status::InvalidUtf8);
+            assert_eq!(gsm7_segment_count(core::ptr::null(), 1, &mut count), Gsm7Status::NullPointer);
+            assert_eq!(gsm7_segment_count(text.as_ptr(), text.len(), &mut count), Gsm7Status::Ok);
+            assert_eq!(count, 1);
+        }
+    }
 }
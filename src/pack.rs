@@ -0,0 +1,227 @@
+#[cfg(any(feature = "futures", feature = "tokio"))]
+use std::io;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(any(feature = "futures", feature = "tokio"))]
+use crate::charset::{decode_next, lookup, Code, TruncatedEscapePolicy, ESC};
+#[cfg(any(feature = "futures", feature = "tokio"))]
+use crate::Gsm7Error;
+
+const CR: u8 = 0x0D;
+
+// Number of whole septets in `len` bytes.
+pub(crate) fn septet_count(len: usize) -> usize {
+    len / 7 * 8 + len % 7 * 8 / 7
+}
+
+// Number of bytes needed to hold `septets` septets.
+pub(crate) const fn byte_count(septets: usize) -> usize {
+    septets / 8 * 7 + (septets % 8 * 7).div_ceil(8)
+}
+
+// The `i`th septet of packed data, if the data is long enough to hold it.
+pub(crate) fn septet_at(bytes: &[u8], i: usize) -> Option<u8> {
+    let bit = i.checked_mul(7)?;
+    let (byte, shift) = (bit / 8, bit % 8);
+    let mut word = (*bytes.get(byte)? >> shift) as u16;
+    if shift > 1 {
+        word |= (*bytes.get(byte + 1)? as u16) << (8 - shift);
+    }
+    Some(word as u8 & 0x7F)
+}
+
+/// Packs septet values, one per byte, into octets with no reference to any character table. Only
+/// the low seven bits of each value are used, and the spare bits of the final octet are zero.
+#[cfg(feature = "alloc")]
+pub fn pack_septets(septets: &[u8]) -> Vec<u8> {
+    let mut packer = Packer::new();
+    let mut out = Vec::with_capacity(byte_count(septets.len()));
+    out.extend(septets.iter().filter_map(|&septet| packer.push(septet)));
+    out.extend(packer.finish_zeroed());
+    out
+}
+
+/// Unpacks every whole septet in `bytes`, one per byte of the result, with no reference to any
+/// character table. When the spare bits of the final octet make a whole septet, it is included,
+/// so the result may need truncating to the UDL.
+#[cfg(feature = "alloc")]
+pub fn unpack_septets(bytes: &[u8]) -> Vec<u8> {
+    (0..septet_count(bytes.len())).filter_map(|i| septet_at(bytes, i)).collect()
+}
+
+// Incremental septet packer, producing the same output as `Gsm7Writer`.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Packer {
+    acc: u16,
+    bits: u8,
+}
+
+impl Packer {
+    pub(crate) const fn new() -> Self {
+        Self { acc: 0, bits: 0 }
+    }
+
+    // A packer whose first septet starts after `fill` zero bits, as when text follows a UDH.
+    #[cfg(feature = "alloc")]
+    pub(crate) const fn with_fill(fill: u8) -> Self {
+        Self { acc: 0, bits: fill }
+    }
+
+    // Adds a septet, returning the byte it completes, if any.
+    pub(crate) fn push(&mut self, septet: u8) -> Option<u8> {
+        self.acc |= ((septet & 0x7F) as u16) << self.bits;
+        self.bits += 7;
+        if self.bits >= 8 {
+            let byte = self.acc as u8;
+            self.acc >>= 8;
+            self.bits -= 8;
+            Some(byte)
+        }
+        else {
+            None
+        }
+    }
+
+    // Adds the septets encoding `c`, appending the bytes they complete to `out`.
+    #[cfg(any(feature = "futures", feature = "tokio"))]
+    pub(crate) fn push_char(&mut self, c: char, out: &mut Vec<u8>) -> Result<(), Gsm7Error> {
+        match lookup(c) {
+            Code::Basic(septet) => out.extend(self.push(septet)),
+            Code::Escaped(septet) => {
+                out.extend(self.push(ESC));
+                out.extend(self.push(septet));
+            },
+            Code::Unencodable => return Err(Gsm7Error::Unencodable(c)),
+        }
+        Ok(())
+    }
+
+    // Number of bits of the current partial byte already used.
+    pub(crate) fn pending_bits(&self) -> u8 {
+        self.bits
+    }
+
+    // The final partial byte, if any, with its spare bits zeroed.
+    pub(crate) fn finish_zeroed(self) -> Option<u8> {
+        if self.bits > 0 {
+            Some(self.acc as u8)
+        }
+        else {
+            None
+        }
+    }
+
+    // The final partial byte, if any, padded the same way as `Gsm7Writer::into_writer` pads it.
+    pub(crate) fn finish(self) -> Option<u8> {
+        match self.bits {
+            0 => None,
+            7 => Some(self.acc as u8 | CR << 7),
+            _ => Some(self.acc as u8),
+        }
+    }
+}
+
+// Incremental septet unpacker. Holds the bits of a partially received septet between calls, so
+// input can arrive in arbitrarily sized chunks.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Unpacker {
+    acc: u16,
+    bits: u8,
+}
+
+#[cfg(feature = "std")]
+impl Unpacker {
+    pub(crate) fn push(&mut self, byte: u8, out: &mut Vec<u8>) {
+        self.acc |= (byte as u16) << self.bits;
+        self.bits += 8;
+        while self.bits >= 7 {
+            out.push(self.acc as u8 & 0x7F);
+            self.acc >>= 7;
+            self.bits -= 7;
+        }
+    }
+
+    pub(crate) fn unpack(&mut self, mut bytes: &[u8], out: &mut Vec<u8>) {
+        out.reserve(bytes.len() / 7 * 8 + 8);
+        while let Some((&byte, rest)) = bytes.split_first() {
+            // Once septet-aligned, every seven bytes hold exactly eight septets.
+            if self.bits == 0 && bytes.len() >= 7 {
+                let mut word = [0; 8];
+                word[..7].copy_from_slice(&bytes[..7]);
+                let word = u64::from_le_bytes(word);
+                out.extend((0..8).map(|i| (word >> (7 * i)) as u8 & 0x7F));
+                bytes = &bytes[7..];
+            }
+            else {
+                self.push(byte, out);
+                bytes = rest;
+            }
+        }
+    }
+}
+
+// Septets unpacked from input that arrives in chunks, for the async adapters, which cannot call
+// back into their reader from inside `decode_next`.
+#[cfg(any(feature = "futures", feature = "tokio"))]
+#[derive(Debug, Default)]
+pub(crate) struct SeptetBuf {
+    unpacker: Unpacker,
+    septets: Vec<u8>,
+    pos: usize,
+    // With a UDL, the septets left to decode and the bytes left to read.
+    limit: Option<(usize, usize)>,
+    eof: bool,
+}
+
+#[cfg(any(feature = "futures", feature = "tokio"))]
+impl SeptetBuf {
+    pub(crate) fn with_udl(udl: usize) -> Self {
+        Self { limit: Some((udl, byte_count(udl))), ..Self::default() }
+    }
+
+    // Whether another chunk must be pushed before the next character can be decoded. An escape
+    // sequence needs two septets, so at least that many are kept buffered until the input ends.
+    pub(crate) fn needs_input(&self) -> bool {
+        self.septets.len() - self.pos < 2 && !self.eof
+    }
+
+    // The most bytes the next chunk may hold without reading past the user data.
+    pub(crate) fn read_len(&self, max: usize) -> usize {
+        self.limit.map_or(max, |(_, bytes)| bytes.min(max))
+    }
+
+    // Adds a chunk of input. An empty chunk marks the end of the input.
+    pub(crate) fn push(&mut self, chunk: &[u8]) {
+        self.septets.drain(..self.pos);
+        self.pos = 0;
+        if chunk.is_empty() {
+            self.eof = true;
+        }
+        if let Some((_, bytes)) = &mut self.limit {
+            *bytes -= chunk.len();
+        }
+        self.unpacker.unpack(chunk, &mut self.septets);
+    }
+
+    pub(crate) fn decode(&mut self) -> Option<io::Result<char>> {
+        let Self { septets, pos, limit, .. } = self;
+        decode_next(|| {
+            if let Some((remaining, _)) = limit {
+                if *remaining == 0 {
+                    return None;
+                }
+                *remaining -= 1;
+            }
+            match septets.get(*pos) {
+                Some(&septet) => {
+                    *pos += 1;
+                    Some(Ok(septet))
+                },
+                None if limit.is_some() => Some(Err(Gsm7Error::UnexpectedEnd.into())),
+                None => None,
+            }
+        }, TruncatedEscapePolicy::Error)
+    }
+}
@@ -0,0 +1,515 @@
+//! SMS-SUBMIT TPDUs carrying GSM 7-bit text, as sent to a modem in PDU mode, and the
+//! SMS-DELIVER and SMS-STATUS-REPORT TPDUs that come back.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+
+use crate::charset::lookup;
+use crate::hex::from_hex;
+use crate::user_data::{
+    is_gsm7_dcs, DecodedUserData, DefaultAlphabet, EncodedUserData, UserDataDecoder, UserDataEncoder,
+};
+use crate::{user_data_octets, Budget, Chars, Gsm7Error};
+
+// Information element identifier for concatenated messages with an 8-bit reference.
+const IEI_CONCAT_8: u8 = 0x00;
+// TP-MTI for SMS-DELIVER, SMS-SUBMIT and SMS-STATUS-REPORT, and TP-UDHI.
+const MTI_MASK: u8 = 0x03;
+const MTI_DELIVER: u8 = 0x00;
+const MTI_SUBMIT: u8 = 0x01;
+const MTI_STATUS_REPORT: u8 = 0x02;
+const UDHI: u8 = 0x40;
+// Type of address: international or unknown number, ISDN numbering plan.
+const TOA_INTERNATIONAL: u8 = 0x91;
+const TOA_UNKNOWN: u8 = 0x81;
+// The type-of-number bits of a type of address, and their values for international and
+// alphanumeric addresses.
+const TON_MASK: u8 = 0x70;
+const TON_INTERNATIONAL: u8 = 0x10;
+const TON_ALPHANUMERIC: u8 = 0x50;
+const MAX_ADDRESS_DIGITS: usize = 20;
+// The status byte of an EF(SMS) record: whether it is in use, whether it holds a message to send
+// rather than one received, and whether that is yet to be read or sent.
+const RECORD_USED: u8 = 0x01;
+const RECORD_OUTGOING: u8 = 0x04;
+const RECORD_PENDING: u8 = 0x02;
+
+/// Error building a PDU.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Error {
+    Gsm7(Gsm7Error),
+    /// The address is not an optional `+` followed by 1 to 20 digits.
+    InvalidAddress,
+    /// The text needs more than 255 parts, or more than a `SegmentedWriter` announces.
+    TooManySegments,
+    /// The TPDU is not of the type expected; holds its TP-MTI.
+    UnsupportedMessageType(u8),
+    /// The result code is not a well-formed `+CMT`, `+CDS`, `+CUSD` or `+CBM`.
+    InvalidResultCode,
+}
+
+impl From<Gsm7Error> for Error {
+    fn from(e: Gsm7Error) -> Self {
+        Error::Gsm7(e)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Gsm7(e) => e.fmt(f),
+            Error::InvalidAddress => f.write_str("invalid destination address"),
+            Error::TooManySegments => f.write_str("text needs too many segments"),
+            Error::UnsupportedMessageType(mti) => write!(f, "unexpected TP-MTI {}", mti),
+            Error::InvalidResultCode => f.write_str("invalid or unsupported result code"),
+        }
+    }
+}
+
+impl core::error::Error for Error {}
+
+/// Builds one SMS-SUBMIT TPDU per part of `text` to `destination`, concatenated with the 8-bit
+/// `reference` if there is more than one part.
+///
+/// The TPDUs have no validity period and no SMSC address; prefix each with `0x00` for a modem to
+/// use its default SMSC.
+pub fn submit_pdus(destination: &str, text: &str, reference: u8) -> Result<Vec<Vec<u8>>, Error> {
+    let address = encode_address(destination)?;
+    let parts = crate::split_for_sms(text)?;
+    let total = u8::try_from(parts.len()).map_err(|_| Error::TooManySegments)?;
+    let mut pdus = Vec::with_capacity(parts.len());
+    for (seq, part) in (1..=total).zip(parts) {
+        let udh = [IEI_CONCAT_8, 3, reference, total, seq];
+        let udh = if total > 1 { Some(&udh[..]) } else { None };
+        let user_data = DefaultAlphabet.encode_user_data(part, udh)?;
+        let mut pdu = Vec::with_capacity(address.len() + user_data.user_data.len() + 5);
+        pdu.push(if udh.is_some() { MTI_SUBMIT | UDHI } else { MTI_SUBMIT });
+        // TP-MR; the modem assigns the message reference.
+        pdu.push(0x00);
+        pdu.extend_from_slice(&address);
+        // TP-PID and TP-DCS.
+        pdu.push(0x00);
+        pdu.push(user_data.dcs);
+        pdu.push(user_data.udl as u8);
+        pdu.extend_from_slice(&user_data.user_data);
+        pdus.push(pdu);
+    }
+    Ok(pdus)
+}
+
+// Encodes a phone number as a TP-DA: digit count, type of address, then swapped BCD digits.
+fn encode_address(address: &str) -> Result<Vec<u8>, Error> {
+    let (toa, digits) = match address.strip_prefix('+') {
+        Some(digits) => (TOA_INTERNATIONAL, digits),
+        None => (TOA_UNKNOWN, address),
+    };
+    if digits.is_empty() || digits.len() > MAX_ADDRESS_DIGITS || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(Error::InvalidAddress);
+    }
+    let mut out = Vec::with_capacity(2 + digits.len().div_ceil(2));
+    out.push(digits.len() as u8);
+    out.push(toa);
+    for pair in digits.as_bytes().chunks(2) {
+        let low = pair[0] - b'0';
+        let high = pair.get(1).map_or(0xF, |b| b - b'0');
+        out.push(high << 4 | low);
+    }
+    Ok(out)
+}
+
+/// Splits text into the user data of SMS parts as it is written, starting a new part whenever a
+/// character would overflow the 153 septets left by an 8-bit reference concatenation header. An
+/// escape pair is never split between parts. Each part is handed to a callback as soon as the
+/// next one starts, with a header announcing the number of parts given to `new`. The first part
+/// is held back until the text outgrows a single SMS, so that text of up to 160 septets is handed
+/// out by `finish` as one SMS with no header.
+#[derive(Clone)]
+pub struct SegmentedWriter<F> {
+    reference: u8,
+    total: u8,
+    // Parts handed out so far.
+    sent: u8,
+    // The first part, while the text still fits a single SMS.
+    first: Option<String>,
+    part: String,
+    // Septets in `part`, and in all the text.
+    septets: usize,
+    written: usize,
+    on_part: F,
+}
+
+impl<F: FnMut(EncodedUserData)> SegmentedWriter<F> {
+    /// Creates a writer whose parts are concatenated with the 8-bit `reference`, announce `total`
+    /// parts, as `segment_count` gives for the text, and are handed to `on_part`.
+    pub fn new(reference: u8, total: u8, on_part: F) -> Self {
+        Self { reference, total, sent: 0, first: None, part: String::new(), septets: 0, written: 0, on_part }
+    }
+
+    /// Appends `c`, returning the number of septets it took. Fails if it has no GSM 7-bit
+    /// encoding or would need more parts than announced.
+    pub fn write_char(&mut self, c: char) -> Result<usize, Error> {
+        let septets = lookup(c).septets().ok_or(Gsm7Error::Unencodable(c))?;
+        let budget = if self.total > 1 { Budget::Concat8 } else { Budget::Single };
+        if self.septets + septets > budget.septets() {
+            if self.started() >= usize::from(self.total) {
+                return Err(Error::TooManySegments);
+            }
+            let part = core::mem::take(&mut self.part);
+            if self.sent == 0 && self.first.is_none() {
+                self.first = Some(part);
+            }
+            else {
+                self.send(&part)?;
+            }
+            self.septets = 0;
+        }
+        self.part.push(c);
+        self.septets += septets;
+        self.written += septets;
+        if self.written > Budget::Single.septets() {
+            if let Some(first) = self.first.take() {
+                self.send(&first)?;
+            }
+        }
+        Ok(septets)
+    }
+
+    /// Appends `s`, returning the number of septets it took. On failure, the text before the
+    /// character that failed has been written.
+    pub fn write_str(&mut self, s: &str) -> Result<usize, Error> {
+        s.chars().try_fold(0, |septets, c| Ok(septets + self.write_char(c)?))
+    }
+
+    /// The number of parts the text written so far needs.
+    pub fn parts(&self) -> usize {
+        if self.written <= Budget::Single.septets() {
+            1
+        }
+        else {
+            self.started()
+        }
+    }
+
+    /// Hands out the rest of the text. Parts announced but not needed are handed out empty, so
+    /// the receiver isn't left waiting for them.
+    pub fn finish(mut self) -> Result<(), Error> {
+        if self.written <= Budget::Single.septets() {
+            let text = self.first.take().unwrap_or_default() + &self.part;
+            (self.on_part)(DefaultAlphabet.encode_user_data(&text, None)?);
+            return Ok(());
+        }
+        let part = core::mem::take(&mut self.part);
+        self.send(&part)?;
+        while self.sent < self.total {
+            self.send("")?;
+        }
+        Ok(())
+    }
+
+    // Parts handed out, held back or being written.
+    fn started(&self) -> usize {
+        usize::from(self.sent) + usize::from(self.first.is_some()) + 1
+    }
+
+    fn send(&mut self, part: &str) -> Result<(), Error> {
+        self.sent += 1;
+        let udh = [IEI_CONCAT_8, 3, self.reference, self.total, self.sent];
+        (self.on_part)(DefaultAlphabet.encode_user_data(part, Some(&udh))?);
+        Ok(())
+    }
+}
+
+/// Shows the writer's position, but not the callback.
+impl<F> fmt::Debug for SegmentedWriter<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SegmentedWriter")
+            .field("reference", &self.reference)
+            .field("total", &self.total)
+            .field("sent", &self.sent)
+            .field("septets", &self.written)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An SMS-DELIVER TPDU, as a modem hands over a received message.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Deliver {
+    /// The sender: digits, with a leading `+` if international, or the text of an alphanumeric
+    /// sender ID.
+    pub originator: String,
+    pub pid: u8,
+    pub dcs: u8,
+    /// TP-SCTS, when the service centre received the message, as sent: seven octets of swapped
+    /// semi-octets.
+    pub timestamp: [u8; 7],
+    pub user_data: DecodedUserData,
+}
+
+// Reads the fields of a TPDU in order.
+struct Fields<'a>(&'a [u8]);
+
+impl<'a> Fields<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Error> {
+        if n > self.0.len() {
+            return Err(Gsm7Error::UnexpectedEnd.into());
+        }
+        let (field, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(field)
+    }
+
+    fn byte(&mut self) -> Result<u8, Error> {
+        Ok(self.take(1)?[0])
+    }
+
+    // An address whose length counts its semi-octets, as TP-OA and TP-RA have.
+    fn address(&mut self) -> Result<String, Error> {
+        let digits = self.byte()? as usize;
+        let toa = self.byte()?;
+        let bytes = self.take(digits.div_ceil(2))?;
+        if toa & TON_MASK == TON_ALPHANUMERIC {
+            return Ok(Chars::with_udl(bytes, digits * 4 / 7).collect::<Result<_, _>>()?);
+        }
+        let mut out = String::with_capacity(digits + 1);
+        if toa & TON_MASK == TON_INTERNATIONAL {
+            out.push('+');
+        }
+        for i in 0..digits {
+            let digit = bytes[i / 2] >> (4 * (i % 2)) & 0xF;
+            if let Some(&c) = b"0123456789*#abc".get(digit as usize) {
+                out.push(c as char);
+            }
+        }
+        Ok(out)
+    }
+}
+
+// Decodes user data in GSM 7-bit text or UCS-2, going by `dcs`.
+fn decode_user_data(dcs: u8, udhi: bool, udl: usize, user_data: &[u8]) -> Result<DecodedUserData, Error> {
+    let is_ucs2 = match dcs >> 4 {
+        0x0..=0x7 => dcs & 0x0C == 0x08,
+        0xE => true,
+        _ => false,
+    };
+    if !is_ucs2 {
+        return Ok(DefaultAlphabet.decode_user_data(dcs, udhi, udl, user_data)?);
+    }
+    let user_data = user_data.get(..udl).ok_or(Gsm7Error::UnexpectedEnd)?;
+    let (udh, text) = match udhi {
+        true => {
+            let udhl = *user_data.first().ok_or(Gsm7Error::UnexpectedEnd)? as usize;
+            let udh = user_data.get(1..1 + udhl).ok_or(Gsm7Error::UnexpectedEnd)?;
+            (Some(udh.to_vec()), &user_data[1 + udhl..])
+        },
+        false => (None, user_data),
+    };
+    Ok(DecodedUserData { text: ucs2(text), udh })
+}
+
+// Decodes UCS-2 text, taken as UTF-16BE, making unpaired surrogates U+FFFD.
+pub(crate) fn ucs2(bytes: &[u8]) -> String {
+    let units = bytes.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]]));
+    char::decode_utf16(units).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)).collect()
+}
+
+/// Parses an SMS-DELIVER TPDU whose text is GSM 7-bit or UCS-2. Unpaired surrogates in UCS-2
+/// text become U+FFFD.
+pub fn parse_deliver(tpdu: &[u8]) -> Result<Deliver, Error> {
+    let mut fields = Fields(tpdu);
+    let first = fields.byte()?;
+    if first & MTI_MASK != MTI_DELIVER {
+        return Err(Error::UnsupportedMessageType(first & MTI_MASK));
+    }
+    let originator = fields.address()?;
+    let pid = fields.byte()?;
+    let dcs = fields.byte()?;
+    let mut timestamp = [0; 7];
+    timestamp.copy_from_slice(fields.take(7)?);
+    let udl = fields.byte()? as usize;
+    let user_data = decode_user_data(dcs, first & UDHI != 0, udl, fields.0)?;
+    Ok(Deliver { originator, pid, dcs, timestamp, user_data })
+}
+
+// Skips the SMSC address that modems put before a TPDU: its length in octets, then the address.
+fn skip_smsc(pdu: &[u8]) -> Result<&[u8], Error> {
+    let len = *pdu.first().ok_or(Gsm7Error::UnexpectedEnd)? as usize;
+    Ok(pdu.get(1 + len..).ok_or(Gsm7Error::UnexpectedEnd)?)
+}
+
+/// Parses an SMS-DELIVER PDU as modems give it in `+CMT` and `+CMGR`, starting with the SMSC
+/// address.
+pub fn parse_deliver_with_smsc(pdu: &[u8]) -> Result<Deliver, Error> {
+    parse_deliver(skip_smsc(pdu)?)
+}
+
+/// An SMS-STATUS-REPORT TPDU, telling whether a message sent with a status report requested
+/// reached its recipient.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StatusReport {
+    /// TP-MR of the SMS-SUBMIT this reports on.
+    pub message_reference: u8,
+    /// The recipient, written as `Deliver::originator` is.
+    pub recipient: String,
+    /// TP-SCTS, when the service centre received the message, as sent.
+    pub timestamp: [u8; 7],
+    /// TP-DT, when the message was delivered or the service centre gave up, as sent.
+    pub discharge_time: [u8; 7],
+    /// TP-ST: 0x00 to 0x1F mean delivered, 0x20 to 0x3F still trying, and above that failed.
+    pub status: u8,
+}
+
+/// Parses an SMS-STATUS-REPORT TPDU, ignoring the optional fields after TP-ST.
+pub fn parse_status_report(tpdu: &[u8]) -> Result<StatusReport, Error> {
+    let mut fields = Fields(tpdu);
+    let first = fields.byte()?;
+    if first & MTI_MASK != MTI_STATUS_REPORT {
+        return Err(Error::UnsupportedMessageType(first & MTI_MASK));
+    }
+    let message_reference = fields.byte()?;
+    let recipient = fields.address()?;
+    let (mut timestamp, mut discharge_time) = ([0; 7], [0; 7]);
+    timestamp.copy_from_slice(fields.take(7)?);
+    discharge_time.copy_from_slice(fields.take(7)?);
+    let status = fields.byte()?;
+    Ok(StatusReport { message_reference, recipient, timestamp, discharge_time, status })
+}
+
+/// Parses an SMS-STATUS-REPORT PDU as modems give it in `+CDS`, starting with the SMSC address.
+pub fn parse_status_report_with_smsc(pdu: &[u8]) -> Result<StatusReport, Error> {
+    parse_status_report(skip_smsc(pdu)?)
+}
+
+/// A record of EF(SMS), the file a SIM stores messages in, from `parse_sms_record`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SmsRecord {
+    Free,
+    /// A received message, and whether it has been read.
+    Received { read: bool, message: Deliver },
+    /// A message to send, whether it has been sent, and its SMS-SUBMIT TPDU without the padding.
+    Submit { sent: bool, tpdu: Vec<u8> },
+}
+
+// Octets of the SMS-SUBMIT TPDU at the start of `tpdu`, going by its fields.
+fn submit_len(tpdu: &[u8]) -> Result<usize, Error> {
+    let mut fields = Fields(tpdu);
+    let first = fields.byte()?;
+    if first & MTI_MASK != MTI_SUBMIT {
+        return Err(Error::UnsupportedMessageType(first & MTI_MASK));
+    }
+    let _message_reference = fields.byte()?;
+    let digits = fields.byte()? as usize;
+    fields.take(1 + digits.div_ceil(2))?;
+    let _pid = fields.byte()?;
+    let dcs = fields.byte()?;
+    // TP-VPF: no validity period, an enhanced one, a relative one or an absolute one.
+    fields.take([0, 7, 1, 7][(first >> 3 & 0x03) as usize])?;
+    let udl = fields.byte()? as usize;
+    let user_data = if is_gsm7_dcs(dcs) { user_data_octets(udl) } else { udl };
+    fields.take(user_data)?;
+    Ok(tpdu.len() - fields.0.len())
+}
+
+/// Parses a record of EF(SMS), as read from a SIM with `AT+CRSM` or from its filesystem: a
+/// status byte, then the SMSC address and the TPDU, padded to 176 bytes with 0xFF. Received
+/// messages are parsed as `parse_deliver` parses them; messages to send are left as TPDUs.
+pub fn parse_sms_record(record: &[u8]) -> Result<SmsRecord, Error> {
+    let status = *record.first().ok_or(Gsm7Error::UnexpectedEnd)?;
+    if status & RECORD_USED == 0 {
+        return Ok(SmsRecord::Free);
+    }
+    let tpdu = skip_smsc(&record[1..])?;
+    let done = status & RECORD_PENDING == 0;
+    if status & RECORD_OUTGOING == 0 {
+        Ok(SmsRecord::Received { read: done, message: parse_deliver(tpdu)? })
+    }
+    else {
+        Ok(SmsRecord::Submit { sent: done, tpdu: tpdu[..submit_len(tpdu)?].to_vec() })
+    }
+}
+
+/// Decoder of the SMS-DELIVER PDUs in a modem log, from `decode_log`.
+#[derive(Clone, Debug)]
+pub struct LogDecoder<I> {
+    lines: I,
+    hex: String,
+    // The TPDU length the last result code gave, which tells where a PDU ends.
+    tpdu_len: Option<usize>,
+}
+
+/// Decodes the SMS-DELIVER PDUs in the lines of a modem log, such as a `minicom` capture. A PDU
+/// is any run of lines of nothing but hex digits, so that one wrapped over several lines is put
+/// back together, and it starts with the SMSC address, as modems give it. Other lines end the
+/// PDU before them; a `+CMT:`, `+CMGR:` or `+CMGL:` line also gives its length, so that the PDU
+/// ends there even without a line after it. Each PDU gives a message, or the error it failed
+/// with.
+pub fn decode_log<I>(lines: I) -> LogDecoder<I::IntoIter>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    LogDecoder { lines: lines.into_iter(), hex: String::new(), tpdu_len: None }
+}
+
+// The TPDU length at the end of a result code that comes before a PDU.
+fn tpdu_len(line: &str) -> Option<usize> {
+    let fields = ["+CMT:", "+CMGR:", "+CMGL:"].iter().find_map(|prefix| line.strip_prefix(prefix))?;
+    fields.rsplit(',').next()?.trim().parse().ok()
+}
+
+impl<I> LogDecoder<I> {
+    // The hex digits of the PDU collected so far, if the last result code says it is complete.
+    fn complete(&mut self) -> Option<String> {
+        let smsc = u8::from_str_radix(self.hex.get(..2)?, 16).ok()? as usize;
+        let digits = (1 + smsc + self.tpdu_len?) * 2;
+        if self.hex.len() < digits {
+            return None;
+        }
+        self.tpdu_len = None;
+        let rest = self.hex.split_off(digits);
+        Some(core::mem::replace(&mut self.hex, rest))
+    }
+
+    fn decode(hex: &str) -> Result<Deliver, Error> {
+        parse_deliver_with_smsc(&from_hex(hex).ok_or(Gsm7Error::InvalidHex)?)
+    }
+}
+
+impl<I> Iterator for LogDecoder<I>
+where
+    I: Iterator,
+    I::Item: AsRef<str>,
+{
+    type Item = Result<Deliver, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(hex) = self.complete() {
+                return Some(Self::decode(&hex));
+            }
+            let line = match self.lines.next() {
+                Some(line) => line,
+                None if self.hex.is_empty() => return None,
+                None => return Some(Self::decode(&core::mem::take(&mut self.hex))),
+            };
+            let line = line.as_ref().trim();
+            if !line.is_empty() && line.bytes().all(|b| b.is_ascii_hexdigit()) {
+                self.hex.push_str(line);
+                continue;
+            }
+            self.tpdu_len = tpdu_len(line);
+            if !self.hex.is_empty() {
+                return Some(Self::decode(&core::mem::take(&mut self.hex)));
+            }
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use alloc::vec::Vec;
+
+use crate::segment::header_septets;
+use crate::{coverage, Alphabet, Budget, Coverage};
+
+// Octets of the UDHL byte, and of an 8-bit reference concatenation element.
+const UDHL_OCTETS: usize = 1;
+const CONCAT_OCTETS: usize = 5;
+
+/// How one part of a planned message is encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PartEncoding {
+    /// GSM 7-bit text with these tables, whose header elements the part has to carry.
+    Gsm7(Alphabet),
+    Ucs2,
+}
+
+/// One part of a planned message, and the text it carries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PlannedPart<'a> {
+    pub encoding: PartEncoding,
+    pub text: &'a str,
+}
+
+// Splits `s` into the parts of a message whose characters cost what `cost` says, or returns
+// `None` if one has no cost. The text is a single part if it fits in `single`, and otherwise
+// parts of at most `concat`.
+fn split(s: &str, single: usize, concat: usize, cost: impl Fn(char) -> Option<usize>) -> Option<Vec<&str>> {
+    let (mut total, mut in_part, mut start) = (0, 0, 0);
+    let mut parts = Vec::new();
+    for (i, c) in s.char_indices() {
+        let n = cost(c)?;
+        total += n;
+        if in_part + n > concat {
+            parts.push(&s[start..i]);
+            start = i;
+            in_part = 0;
+        }
+        in_part += n;
+    }
+    if total <= single {
+        return Some(alloc::vec![s]);
+    }
+    parts.push(&s[start..]);
+    Some(parts)
+}
+
+// The longest prefix of `s` whose characters all have a cost and cost at most `budget` in all.
+fn prefix(s: &str, budget: usize, cost: impl Fn(char) -> Option<usize>) -> &str {
+    let mut spent = 0;
+    for (i, c) in s.char_indices() {
+        match cost(c) {
+            Some(n) if spent + n <= budget => spent += n,
+            _ => return &s[..i],
+        }
+    }
+    s
+}
+
+// Septets of text left in a single part and in a concatenated part by the header elements
+// announcing `alphabet`.
+fn gsm7_budgets(alphabet: &Alphabet) -> (usize, usize) {
+    let elements: usize = alphabet.header_elements().map(|element| element.len()).sum();
+    let single = match elements {
+        0 => Budget::Single.septets(),
+        _ => Budget::Single.septets() - header_septets(UDHL_OCTETS + elements),
+    };
+    (single, Budget::Single.septets() - header_septets(UDHL_OCTETS + CONCAT_OCTETS + elements))
+}
+
+fn ucs2_units(c: char) -> Option<usize> {
+    Some(c.len_utf16())
+}
+
+/// Plans how to send `s` in as few parts as possible, trying GSM 7-bit text with every
+/// combination of tables in `Alphabet::all`, UCS-2, and a mix of default-alphabet GSM 7-bit
+/// parts and UCS-2 parts, which suits long text with a few characters GSM 7-bit lacks. Each part
+/// has its own DCS, so a mix is valid, but some handsets show it badly. Ties go to the default
+/// alphabet, then the national tables in the order of `Alphabet::all`, then UCS-2, and a mix is
+/// chosen only when it needs fewer parts than all of them.
+pub fn plan_message<'a>(s: &'a str) -> Vec<PlannedPart<'a>> {
+    let mut best: Option<Vec<PlannedPart<'a>>> = None;
+    let mut consider = |plan: Vec<PlannedPart<'a>>| {
+        if best.as_ref().is_none_or(|best| plan.len() < best.len()) {
+            best = Some(plan);
+        }
+    };
+    for alphabet in Alphabet::all() {
+        let (single, concat) = gsm7_budgets(&alphabet);
+        if let Some(parts) = split(s, single, concat, |c| alphabet.septets(c)) {
+            let encoding = PartEncoding::Gsm7(alphabet);
+            consider(parts.into_iter().map(|text| PlannedPart { encoding, text }).collect());
+        }
+    }
+    let ucs2_budgets = (Budget::Single.ucs2_chars(), Budget::Concat8.ucs2_chars());
+    if let Some(parts) = split(s, ucs2_budgets.0, ucs2_budgets.1, ucs2_units) {
+        consider(parts.into_iter().map(|text| PlannedPart { encoding: PartEncoding::Ucs2, text }).collect());
+    }
+
+    let mut mixed = Vec::new();
+    let mut rest = s;
+    while !rest.is_empty() {
+        let gsm7 = prefix(rest, Budget::Concat8.septets(), |c| Alphabet::DEFAULT.septets(c));
+        let ucs2 = prefix(rest, ucs2_budgets.1, ucs2_units);
+        let part = if gsm7.len() >= ucs2.len() {
+            PlannedPart { encoding: PartEncoding::Gsm7(Alphabet::DEFAULT), text: gsm7 }
+        }
+        else {
+            PlannedPart { encoding: PartEncoding::Ucs2, text: ucs2 }
+        };
+        rest = &rest[part.text.len()..];
+        mixed.push(part);
+    }
+    if mixed.len() > 1 {
+        consider(mixed);
+    }
+    best.unwrap_or_default()
+}
+
+/// What one character costs in each encoding, from `advise`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CharCost {
+    /// The byte offset of the character in the text.
+    pub offset: usize,
+    pub char: char,
+    /// Septets under each combination of tables in `Alphabet::all`, in that order, or `None` where
+    /// it has no encoding. The first is the default alphabet.
+    pub septets: Vec<Option<usize>>,
+    /// UTF-16 code units in UCS-2: two for a character outside the Basic Multilingual Plane.
+    pub ucs2_units: usize,
+}
+
+impl CharCost {
+    /// Whether no combination of tables can encode the character, so that only UCS-2 can send it.
+    pub fn forces_ucs2(&self) -> bool {
+        self.septets.iter().all(Option::is_none)
+    }
+}
+
+/// Why a text costs what it does, from `advise`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Advice<'a> {
+    pub chars: Vec<CharCost>,
+    /// How well each combination of tables covers the text, as `coverage` gives it.
+    pub coverage: Vec<Coverage>,
+    /// The cheapest way to send the text, as `plan_message` gives it.
+    pub plan: Vec<PlannedPart<'a>>,
+}
+
+impl Advice<'_> {
+    /// The characters that cost more than one septet in the default alphabet: those from the
+    /// extension table, and those it can't encode at all, which force UCS-2 unless a national
+    /// table has them.
+    pub fn costly_chars(&self) -> impl Iterator<Item = &CharCost> {
+        self.chars.iter().filter(|cost| cost.septets[0] != Some(1))
+    }
+}
+
+/// Breaks down what each character of `s` costs under the default alphabet, each national table
+/// and UCS-2, with the cheapest plan for sending it, to explain why a message takes the parts it
+/// does.
+pub fn advise(s: &str) -> Advice<'_> {
+    let chars = s
+        .char_indices()
+        .map(|(offset, c)| CharCost {
+            offset,
+            char: c,
+            septets: Alphabet::all().map(|alphabet| alphabet.septets(c)).collect(),
+            ucs2_units: c.len_utf16(),
+        })
+        .collect();
+    Advice { chars, coverage: coverage(s), plan: plan_message(s) }
+}
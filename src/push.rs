@@ -0,0 +1,218 @@
+use crate::charset::{decode_next, lookup, Code, TruncatedEscapePolicy, ESC};
+use crate::error::trace_decode_error;
+use crate::pack::Packer;
+use crate::Gsm7Error;
+
+const CAPACITY: usize = 64;
+
+/// Incremental decoder that performs no I/O: packed bytes are pushed in as they arrive, in chunks
+/// of any size, and decoded characters are taken out. Needs neither `std` nor an allocator.
+#[derive(Clone, Debug)]
+pub struct Decoder {
+    septets: [u8; CAPACITY],
+    start: usize,
+    len: usize,
+    acc: u16,
+    bits: u8,
+    remaining: Option<usize>,
+    // Septets taken before `start`, for error reporting.
+    offset: usize,
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder {
+    pub const fn new() -> Self {
+        Self { septets: [0; CAPACITY], start: 0, len: 0, acc: 0, bits: 0, remaining: None, offset: 0 }
+    }
+
+    /// Creates a decoder that accepts exactly the bytes holding `udl` septets.
+    pub const fn with_udl(udl: usize) -> Self {
+        Self { remaining: Some(udl), ..Self::new() }
+    }
+
+    /// Adds packed bytes, returning how many were consumed. Stops early when the internal buffer
+    /// is full, in which case the rest can be pushed after calling `take_chars`, or when the UDL
+    /// given to `with_udl` has been reached, in which case the rest is not user data.
+    pub fn push_bytes(&mut self, bytes: &[u8]) -> usize {
+        self.septets.copy_within(self.start..self.len, 0);
+        self.len -= self.start;
+        self.start = 0;
+        let mut consumed = 0;
+        for &byte in bytes {
+            // A byte completes at most two septets.
+            if self.is_complete() || self.len + 2 > CAPACITY {
+                break;
+            }
+            self.acc |= (byte as u16) << self.bits;
+            self.bits += 8;
+            while self.bits >= 7 && !self.is_complete() {
+                self.septets[self.len] = self.acc as u8 & 0x7F;
+                self.len += 1;
+                self.acc >>= 7;
+                self.bits -= 7;
+                if let Some(remaining) = &mut self.remaining {
+                    *remaining -= 1;
+                }
+            }
+            consumed += 1;
+        }
+        consumed
+    }
+
+    /// Iterator over the characters decoded so far. An escape septet at the end of the input so
+    /// far is kept until the septet after it arrives.
+    pub fn take_chars(&mut self) -> TakeChars<'_> {
+        TakeChars { decoder: self }
+    }
+
+    /// Whether all the septets of the UDL given to `with_udl` have been pushed.
+    pub fn is_complete(&self) -> bool {
+        self.remaining == Some(0)
+    }
+
+    /// Checks that the input ended cleanly, with no escape sequence left incomplete and, given a
+    /// UDL, no septets missing.
+    pub fn finish(&self) -> Result<(), Gsm7Error> {
+        if self.remaining.is_some_and(|n| n > 0) {
+            Err(Gsm7Error::UnexpectedEnd)
+        }
+        else if self.septets[self.start..self.len] == [ESC] {
+            Err(Gsm7Error::TruncatedEscape)
+        }
+        else {
+            Ok(())
+        }
+    }
+}
+
+/// Characters taken from a `Decoder`.
+#[derive(Debug)]
+pub struct TakeChars<'a> {
+    decoder: &'a mut Decoder,
+}
+
+impl<'a> Iterator for TakeChars<'a> {
+    type Item = Result<char, Gsm7Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let Decoder { septets, start, len, offset, .. } = &mut *self.decoder;
+        if septets[*start..*len] == [ESC] {
+            return None;
+        }
+        let septet_offset = *offset;
+        let c = decode_next(|| {
+            let septet = *septets[*start..*len].first()?;
+            *start += 1;
+            *offset += 1;
+            Some(Ok(septet))
+        }, TruncatedEscapePolicy::Error);
+        if let Some(Err(e)) = &c {
+            trace_decode_error(e, septet_offset);
+        }
+        c
+    }
+}
+
+const CR: u8 = 0x0D;
+
+/// How `Encoder::finish` fills the spare bits of the final byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Padding {
+    /// Leave the spare bits zero.
+    Zero,
+    /// Follow 3GPP 23.038: fill seven spare bits with CR, so they aren't read as an '@', and add
+    /// a second CR after a CR that ends exactly on a byte boundary, so the first isn't taken for
+    /// padding.
+    Cr,
+}
+
+/// Incremental encoder that performs no I/O, the counterpart of `Decoder`: text is pushed in and
+/// packed bytes are taken out. Needs neither `std` nor an allocator.
+#[derive(Clone, Debug)]
+pub struct Encoder {
+    packer: Packer,
+    bytes: [u8; CAPACITY],
+    len: usize,
+    ends_with_cr: bool,
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Encoder {
+    pub const fn new() -> Self {
+        Self { packer: Packer::new(), bytes: [0; CAPACITY], len: 0, ends_with_cr: false }
+    }
+
+    /// Adds a character. Fails with `BufferTooSmall` if there is no room for it until
+    /// `take_bytes` is called.
+    pub fn push_char(&mut self, c: char) -> Result<(), Gsm7Error> {
+        // A character completes at most two bytes, and `finish` may need two more.
+        if self.len + 4 > CAPACITY {
+            return Err(Gsm7Error::BufferTooSmall);
+        }
+        match lookup(c) {
+            Code::Basic(septet) => self.push(septet),
+            Code::Escaped(septet) => {
+                self.push(ESC);
+                self.push(septet);
+            },
+            Code::Unencodable => return Err(Gsm7Error::Unencodable(c)),
+        }
+        self.ends_with_cr = c == '\r';
+        Ok(())
+    }
+
+    /// Adds as much of `s` as there is room for, returning the length in bytes of the part
+    /// added. The rest can be pushed after calling `take_bytes`.
+    pub fn push_str(&mut self, s: &str) -> Result<usize, Gsm7Error> {
+        for (i, c) in s.char_indices() {
+            match self.push_char(c) {
+                Ok(()) => (),
+                Err(Gsm7Error::BufferTooSmall) => return Ok(i),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(s.len())
+    }
+
+    /// Takes the bytes completed so far.
+    pub fn take_bytes(&mut self) -> &[u8] {
+        let len = core::mem::take(&mut self.len);
+        &self.bytes[..len]
+    }
+
+    /// Pads the final byte and takes the remaining bytes, leaving the encoder ready for the next
+    /// message.
+    pub fn finish(&mut self, padding: Padding) -> &[u8] {
+        if padding == Padding::Cr {
+            match self.packer.pending_bits() {
+                1 => self.push(CR),
+                0 if self.ends_with_cr => self.push(CR),
+                _ => (),
+            }
+        }
+        if let Some(byte) = core::mem::take(&mut self.packer).finish_zeroed() {
+            self.bytes[self.len] = byte;
+            self.len += 1;
+        }
+        self.ends_with_cr = false;
+        self.take_bytes()
+    }
+
+    fn push(&mut self, septet: u8) {
+        if let Some(byte) = self.packer.push(septet) {
+            self.bytes[self.len] = byte;
+            self.len += 1;
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Python bindings, built as the `gsm7` extension module (e.g. with `maturin`) from a `cdylib`
+//! crate that depends on this one with the `python` feature enabled.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use crate::{segment_count, Chars, Gsm7Error, Gsm7String};
+
+fn py_error(e: Gsm7Error) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+/// Encodes `text`, returning the packed bytes.
+#[pyfunction(name = "encode")]
+pub fn encode_py<'py>(py: Python<'py>, text: &str) -> PyResult<Bound<'py, PyBytes>> {
+    let packed = crate::encode(text).map_err(py_error)?;
+    Ok(PyBytes::new(py, &packed))
+}
+
+/// Decodes packed bytes, reading exactly `udl` septets if given.
+#[pyfunction(name = "decode", signature = (data, udl = None))]
+pub fn decode_py(data: &[u8], udl: Option<usize>) -> PyResult<String> {
+    let chars = match udl {
+        Some(udl) => Chars::with_udl(data, udl),
+        None => Chars::new(data),
+    };
+    chars.collect::<Result<_, _>>().map_err(py_error)
+}
+
+/// Number of SMS segments needed to send `text`.
+#[pyfunction(name = "segment_count")]
+pub fn segment_count_py(text: &str) -> PyResult<usize> {
+    segment_count(text).map_err(py_error)
+}
+
+/// Character, septet and segment counts for `text`.
+#[pyfunction]
+pub fn analyze(text: &str) -> Analysis {
+    Analysis {
+        characters: text.chars().count(),
+        septets: text.parse::<Gsm7String>().ok().map(|s| s.septet_len()),
+        segments: segment_count(text).ok(),
+    }
+}
+
+#[pyclass(frozen, skip_from_py_object, module = "gsm7")]
+#[derive(Clone, Copy, Debug)]
+pub struct Analysis {
+    #[pyo3(get)]
+    characters: usize,
+    /// Encoded length in septets, or `None` if the text cannot be encoded.
+    #[pyo3(get)]
+    septets: Option<usize>,
+    #[pyo3(get)]
+    segments: Option<usize>,
+}
+
+#[pymethods]
+impl Analysis {
+    #[getter]
+    pub fn encodable(&self) -> bool {
+        self.septets.is_some()
+    }
+
+    fn __repr__(&self) -> String {
+        let py = |n: Option<usize>| n.map_or_else(|| "None".to_string(), |n| n.to_string());
+        format!("Analysis(characters={}, septets={}, segments={})", self.characters, py(self.septets), py(self.segments))
+    }
+}
+
+#[pymodule]
+fn gsm7(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(encode_py, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_py, m)?)?;
+    m.add_function(wrap_pyfunction!(segment_count_py, m)?)?;
+    m.add_function(wrap_pyfunction!(analyze, m)?)?;
+    m.add_class::<Analysis>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn analyze_counts_segments() {
+        let analysis = super::analyze(&"€".repeat(81));
+        assert_eq!(analysis.characters, 81);
+        assert_eq!(analysis.septets, Some(162));
+        assert_eq!(analysis.segments, Some(2));
+        assert!(!super::analyze("ú").encodable());
+    }
+}
@@ -0,0 +1,468 @@
+use std::{fmt, io, mem};
+
+use bitstream_io::{BitRead, BitReader, Endianness, LittleEndian};
+
+use crate::charset::{decode_next, ControlFilter, ControlPolicy, TruncatedEscapePolicy};
+use crate::error::{trace_decode_error, DecodeError};
+use crate::pack::Unpacker;
+use crate::Gsm7Error;
+
+// Either a failure of the source, passed on as is, or a decoding error, which gets its offset.
+enum ReadError {
+    Io(io::Error),
+    Decode(Gsm7Error),
+}
+
+impl From<Gsm7Error> for ReadError {
+    fn from(e: Gsm7Error) -> Self {
+        ReadError::Decode(e)
+    }
+}
+
+impl ReadError {
+    fn at(self, septet: usize) -> io::Error {
+        match self {
+            ReadError::Io(e) => e,
+            ReadError::Decode(error) => {
+                trace_decode_error(&error, septet);
+                io::Error::new(error.io_kind(), DecodeError { error, septet })
+            },
+        }
+    }
+}
+
+/// Decoder over any bit reader, so that GSM 7-bit text can be read from the middle of a larger
+/// bit-level structure and the bit reader taken back afterwards with `into_bit_reader`. Most
+/// callers want `Gsm7Reader`, which reads from an `io::Read`.
+#[derive(Clone)]
+pub struct Gsm7BitReader<B: BitRead> {
+    reader: B,
+    remaining: Option<usize>,
+    limit: Option<usize>,
+    // Septets read so far.
+    offset: usize,
+    truncated_escape: TruncatedEscapePolicy,
+    nul_terminated: bool,
+    ended: bool,
+    control: ControlFilter,
+}
+
+/// Decoder over an `io::Read`. Septets are packed least significant bit first, as GSM packs them,
+/// unless `E` is `BigEndian`, which some stacks use.
+pub type Gsm7Reader<R, E = LittleEndian> = Gsm7BitReader<BitReader<R, E>>;
+
+impl<B: BitRead> Gsm7BitReader<B> {
+    /// Creates a reader that starts at `reader`'s current position, which need not be byte aligned.
+    pub fn with_bit_reader(reader: B) -> Self {
+        Self {
+            reader,
+            remaining: None,
+            limit: None,
+            offset: 0,
+            truncated_escape: TruncatedEscapePolicy::Error,
+            nul_terminated: false,
+            ended: false,
+            control: ControlFilter::default(),
+        }
+    }
+
+    /// Decodes exactly `udl` septets, as `with_udl` does.
+    pub fn udl(self, udl: usize) -> Self {
+        Self { limit: Some(udl), ..self }
+    }
+
+    /// Sets what to do when the input ends straight after an escape.
+    pub fn truncated_escape(self, policy: TruncatedEscapePolicy) -> Self {
+        Self { truncated_escape: policy, ..self }
+    }
+
+    /// Ends the text at the first septet 0x00 that starts a character, which (U)SIM Toolkit and
+    /// some older fields use as a terminator rather than as `@`.
+    pub fn nul_terminated(self) -> Self {
+        Self { nul_terminated: true, ..self }
+    }
+
+    /// Sets what to do with control characters other than those in `allowed`.
+    pub fn control_chars(self, policy: ControlPolicy, allowed: &str) -> Self {
+        Self { control: ControlFilter::new(policy, allowed), ..self }
+    }
+
+    /// Decodes the rest of the text, allocating once when the length is known.
+    pub fn decode_all(self) -> io::Result<String> {
+        let mut out = String::with_capacity(self.size_hint().1.unwrap_or(0));
+        for c in self {
+            out.push(c?);
+        }
+        Ok(out)
+    }
+
+    /// Gives back the bit reader, positioned after the last septet read.
+    pub fn into_bit_reader(self) -> B {
+        self.reader
+    }
+
+    fn read_septet(&mut self) -> Option<Result<u8, ReadError>> {
+        if self.limit == Some(0) {
+            return None;
+        }
+        match self.reader.read(7) {
+            Ok(septet) => {
+                if let Some(limit) = self.limit.as_mut() {
+                    *limit -= 1;
+                }
+                if let Some(remaining) = self.remaining.as_mut() {
+                    *remaining = remaining.saturating_sub(7);
+                }
+                self.offset += 1;
+                Some(Ok(septet))
+            },
+            // Short of the UDL: an error once, and then the end of the text.
+            Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                self.ended = true;
+                self.limit.map(|_| Err(ReadError::Decode(Gsm7Error::UnexpectedEnd)))
+            },
+            Err(e) => Some(Err(ReadError::Io(e))),
+        }
+    }
+
+    fn septets_left(&self) -> Option<usize> {
+        self.limit.or_else(|| self.remaining.map(|bits| bits / 7))
+    }
+}
+
+impl<R: io::Read> Gsm7Reader<R> {
+    pub fn new(reader: R) -> Self {
+        Self::from(BitReader::new(reader))
+    }
+
+    /// Creates a reader over a source that will yield exactly `len` bytes, which lets the
+    /// iterator report a useful `size_hint`.
+    pub fn with_len(reader: R, len: usize) -> Self {
+        Self { remaining: Some(len.saturating_mul(8)), ..Self::new(reader) }
+    }
+
+    /// Creates a reader that decodes exactly `udl` septets. Running out of input before then is
+    /// an error rather than the end of the text.
+    pub fn with_udl(reader: R, udl: usize) -> Self {
+        Self::new(reader).udl(udl)
+    }
+
+    /// Reads a TP-UDL byte from `reader` and creates a reader that decodes that many septets of
+    /// the UD after it, as for `UDL + UD` taken whole from a PDU.
+    pub fn with_udl_prefix(mut reader: R) -> io::Result<Self> {
+        let mut udl = [0];
+        reader.read_exact(&mut udl)?;
+        Ok(Self::with_udl(reader, udl[0] as usize))
+    }
+}
+
+impl<'a> Gsm7Reader<&'a [u8]> {
+    /// Creates a reader over packed `bytes`, knowing their length for `size_hint`. `Chars` decodes
+    /// a slice faster, and can also run backwards.
+    pub fn from_slice(bytes: &'a [u8]) -> Self {
+        Self::with_len(bytes, bytes.len())
+    }
+}
+
+impl<R: io::Read, E: Endianness> Gsm7Reader<R, E> {
+    /// Creates a reader that unpacks septets in `endian`'s bit order.
+    pub fn endian(reader: R, endian: E) -> Self {
+        Self::from(BitReader::endian(reader, endian))
+    }
+
+    /// Starts over on a new source, as if created by `new` but keeping the truncated-escape
+    /// policy and terminator, and returns the old source.
+    pub fn reset(&mut self, reader: R) -> R {
+        let old = mem::replace(&mut self.reader, BitReader::new(reader));
+        self.remaining = None;
+        self.limit = None;
+        self.offset = 0;
+        self.ended = false;
+        old.into_reader()
+    }
+}
+
+impl<R: io::Read + io::Seek, E: Endianness> Gsm7Reader<R, E> {
+    /// Moves to the `n`th septet, counting from where the reader started, by seeking the source.
+    /// With a UDL, `n` may be at most the UDL.
+    pub fn seek_to_septet(&mut self, n: usize) -> io::Result<()> {
+        if self.limit.is_some_and(|limit| n > limit + self.offset) {
+            return Err(io::ErrorKind::InvalidInput.into());
+        }
+        // Where the reader started in the source, in bits, which allows for a header or fill
+        // bits read before it and for a source that was not at its start.
+        let start = self.reader.position_in_bits()? - self.offset as u64 * 7;
+        let bit = n * 7;
+        self.reader.seek_bits(io::SeekFrom::Start(start + bit as u64))?;
+        if let Some(limit) = self.limit.as_mut() {
+            *limit = *limit + self.offset - n;
+        }
+        if let Some(remaining) = self.remaining.as_mut() {
+            *remaining = (*remaining + self.offset * 7).saturating_sub(bit);
+        }
+        self.offset = n;
+        self.ended = false;
+        Ok(())
+    }
+
+    /// Moves back to where the reader started.
+    pub fn rewind(&mut self) -> io::Result<()> {
+        self.seek_to_septet(0)
+    }
+
+    /// Moves to the `n`th character, counting from where the reader started. Characters take
+    /// one or two septets, so this seeks back to the start and skips `n` characters.
+    pub fn seek_to_char(&mut self, n: usize) -> io::Result<()> {
+        self.seek_to_septet(0)?;
+        for _ in 0..n {
+            self.next().unwrap_or_else(|| Err(io::ErrorKind::UnexpectedEof.into()))?;
+        }
+        Ok(())
+    }
+}
+
+/// Shows the reader's position and limits, but not the source.
+impl<B: BitRead> fmt::Debug for Gsm7BitReader<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Gsm7Reader")
+            .field("septets_read", &self.offset)
+            .field("bits_remaining", &self.remaining)
+            .field("septets_remaining", &self.limit)
+            .field("byte_aligned", &self.reader.byte_aligned())
+            .field("truncated_escape", &self.truncated_escape)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<R: io::Read, E: Endianness> From<BitReader<R, E>> for Gsm7Reader<R, E> {
+    fn from(reader: BitReader<R, E>) -> Self {
+        Self::with_bit_reader(reader)
+    }
+}
+
+impl<B: BitRead> Iterator for Gsm7BitReader<B> {
+    type Item = io::Result<char>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.ended {
+            return None;
+        }
+        let start = self.offset;
+        let (policy, nul_terminated) = (self.truncated_escape, self.nul_terminated);
+        let mut first = true;
+        let mut ended = false;
+        let c = decode_next(
+            || {
+                let septet = self.read_septet();
+                if mem::take(&mut first) && nul_terminated && matches!(septet, Some(Ok(0))) {
+                    ended = true;
+                    return None;
+                }
+                septet
+            },
+            policy,
+        );
+        self.ended |= ended;
+        let control = self.control;
+        Some(c?.and_then(|c| Ok(control.apply(c)?)).map_err(|e| e.at(start)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.ended {
+            return (0, Some(0));
+        }
+        match self.septets_left() {
+            // A terminator may come at any septet.
+            Some(septets) if self.nul_terminated => (0, Some(septets)),
+            // Every character takes one or two septets.
+            Some(septets) => (septets.div_ceil(2), Some(septets)),
+            None => (0, None),
+        }
+    }
+}
+
+impl<'a> From<&'a [u8]> for Gsm7Reader<&'a [u8]> {
+    fn from(bytes: &'a [u8]) -> Self {
+        Self::from_slice(bytes)
+    }
+}
+
+/// Decoder for sources that implement `BufRead`. Unpacks whole buffers at a time rather than
+/// reading bit by bit, which makes it considerably faster than `Gsm7Reader` for large inputs.
+#[derive(Clone)]
+pub struct Gsm7BufReader<R: io::BufRead> {
+    reader: R,
+    unpacker: Unpacker,
+    septets: Vec<u8>,
+    pos: usize,
+    // Septets unpacked before the current buffer, and bytes consumed from the source.
+    offset: usize,
+    consumed: u64,
+    truncated_escape: TruncatedEscapePolicy,
+    nul_terminated: bool,
+    ended: bool,
+    control: ControlFilter,
+}
+
+impl<R: io::BufRead> Gsm7BufReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            unpacker: Unpacker::default(),
+            septets: Vec::new(),
+            pos: 0,
+            offset: 0,
+            consumed: 0,
+            truncated_escape: TruncatedEscapePolicy::Error,
+            nul_terminated: false,
+            ended: false,
+            control: ControlFilter::default(),
+        }
+    }
+
+    /// Starts over on a new source, keeping the buffer, the truncated-escape policy and the
+    /// terminator, and returns the old source.
+    pub fn reset(&mut self, reader: R) -> R {
+        self.unpacker = Unpacker::default();
+        self.septets.clear();
+        self.pos = 0;
+        self.offset = 0;
+        self.consumed = 0;
+        self.ended = false;
+        mem::replace(&mut self.reader, reader)
+    }
+
+    /// Sets what to do when the input ends straight after an escape.
+    pub fn truncated_escape(self, policy: TruncatedEscapePolicy) -> Self {
+        Self { truncated_escape: policy, ..self }
+    }
+
+    /// Ends the text at the first septet 0x00 that starts a character, which (U)SIM Toolkit and
+    /// some older fields use as a terminator rather than as `@`.
+    pub fn nul_terminated(self) -> Self {
+        Self { nul_terminated: true, ..self }
+    }
+
+    /// Sets what to do with control characters other than those in `allowed`.
+    pub fn control_chars(self, policy: ControlPolicy, allowed: &str) -> Self {
+        Self { control: ControlFilter::new(policy, allowed), ..self }
+    }
+
+    /// Decodes the rest of the text.
+    pub fn decode_all(self) -> io::Result<String> {
+        self.collect()
+    }
+
+    fn read_septet(&mut self) -> Option<io::Result<u8>> {
+        while self.pos == self.septets.len() {
+            self.offset += self.septets.len();
+            self.septets.clear();
+            self.pos = 0;
+            let buf = match self.reader.fill_buf() {
+                Ok(buf) => buf,
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Some(Err(e)),
+            };
+            if buf.is_empty() {
+                return None;
+            }
+            self.unpacker.unpack(buf, &mut self.septets);
+            let len = buf.len();
+            self.reader.consume(len);
+            self.consumed += len as u64;
+        }
+        let septet = self.septets[self.pos];
+        self.pos += 1;
+        Some(Ok(septet))
+    }
+}
+
+impl<R: io::BufRead + io::Seek> Gsm7BufReader<R> {
+    /// Moves back to where the reader started, by seeking the source.
+    pub fn rewind(&mut self) -> io::Result<()> {
+        self.reader.seek(io::SeekFrom::Current(-(self.consumed as i64)))?;
+        self.unpacker = Unpacker::default();
+        self.septets.clear();
+        self.pos = 0;
+        self.offset = 0;
+        self.consumed = 0;
+        self.ended = false;
+        Ok(())
+    }
+}
+
+/// Shows the reader's position, but not the source.
+impl<R: io::BufRead> fmt::Debug for Gsm7BufReader<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Gsm7BufReader")
+            .field("septets_read", &(self.offset + self.pos))
+            .field("septets_buffered", &(self.septets.len() - self.pos))
+            .field("bytes_consumed", &self.consumed)
+            .field("truncated_escape", &self.truncated_escape)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<R: io::BufRead> Iterator for Gsm7BufReader<R> {
+    type Item = io::Result<char>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.ended {
+            return None;
+        }
+        let start = self.offset + self.pos;
+        let (policy, nul_terminated) = (self.truncated_escape, self.nul_terminated);
+        let mut first = true;
+        let mut ended = false;
+        let c = decode_next(
+            || {
+                let septet = self.read_septet();
+                if mem::take(&mut first) && nul_terminated && matches!(septet, Some(Ok(0))) {
+                    ended = true;
+                    return None;
+                }
+                septet.map(|r| r.map_err(ReadError::Io))
+            },
+            policy,
+        );
+        self.ended |= ended;
+        let control = self.control;
+        Some(c?.and_then(|c| Ok(control.apply(c)?)).map_err(|e| e.at(start)))
+    }
+}
+
+/// Iterator over the raw septet values of exactly `udl` septets.
+pub struct Septets<R: io::Read> {
+    reader: BitReader<R, LittleEndian>,
+    remaining: usize,
+}
+
+impl<R: io::Read> Septets<R> {
+    pub fn new(reader: R, udl: usize) -> Self {
+        Self { reader: BitReader::new(reader), remaining: udl }
+    }
+}
+
+impl<R: io::Read> Iterator for Septets<R> {
+    type Item = io::Result<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        Some(self.reader.read(7))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<R: io::Read> ExactSizeIterator for Septets<R> {}
+
+impl<R: io::Read> fmt::Debug for Septets<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Septets").field("remaining", &self.remaining).finish_non_exhaustive()
+    }
+}
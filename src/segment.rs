@@ -0,0 +1,168 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use crate::charset::lookup;
+use crate::pack::byte_count;
+use crate::Gsm7Error;
+
+/// The user data available in one SMS, depending on whether it is part of a concatenated message
+/// and so has to carry a concatenation header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Budget {
+    /// A single SMS: 160 septets, 140 octets or 70 UCS-2 characters.
+    Single,
+    /// A part with an 8-bit reference concatenation header, which takes six octets: 153 septets,
+    /// 134 octets or 67 UCS-2 characters.
+    Concat8,
+    /// A part with a 16-bit reference concatenation header, which takes seven octets: 152
+    /// septets, 133 octets or 66 UCS-2 characters.
+    Concat16,
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Budget {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(*u.choose(&[Budget::Single, Budget::Concat8, Budget::Concat16])?)
+    }
+}
+
+impl Budget {
+    /// Septets of GSM 7-bit text, after the header and the fill bits that align the text to a
+    /// septet boundary.
+    pub const fn septets(self) -> usize {
+        match self {
+            Budget::Single => 160,
+            Budget::Concat8 => 153,
+            Budget::Concat16 => 152,
+        }
+    }
+
+    /// Octets of 8-bit data.
+    pub const fn octets(self) -> usize {
+        match self {
+            Budget::Single => 140,
+            Budget::Concat8 => 134,
+            Budget::Concat16 => 133,
+        }
+    }
+
+    /// UCS-2 characters, each of which takes two octets.
+    pub const fn ucs2_chars(self) -> usize {
+        self.octets() / 2
+    }
+
+    /// Whether `s` fits as GSM 7-bit text. Fails if it has a character with no GSM 7-bit encoding.
+    pub fn fits(self, s: &str) -> Result<bool, Gsm7Error> {
+        Ok(truncate_to_septets(s, self.septets())?.len() == s.len())
+    }
+}
+
+/// Septets a user data header of `header_octets` octets takes, counting the UDHL byte and the
+/// fill bits that start the text on a septet boundary. No header takes none.
+pub const fn header_septets(header_octets: usize) -> usize {
+    (header_octets * 8).div_ceil(7)
+}
+
+/// Fill bits between a user data header of `header_octets` octets, counting the UDHL byte, and
+/// the text: from 0 to 6.
+pub const fn fill_bits(header_octets: usize) -> u8 {
+    (header_septets(header_octets) * 7 - header_octets * 8) as u8
+}
+
+/// The TP-UDL of `text_septets` septets of text after a header of `header_octets` octets,
+/// counting the UDHL byte.
+pub const fn udl(header_octets: usize, text_septets: usize) -> usize {
+    header_septets(header_octets) + text_septets
+}
+
+/// The septets of text in user data with TP-UDL `udl` and a header of `header_octets` octets,
+/// counting the UDHL byte, or `None` if the UDL is too short to hold the header.
+pub const fn text_septets(header_octets: usize, udl: usize) -> Option<usize> {
+    udl.checked_sub(header_septets(header_octets))
+}
+
+/// Octets that user data with TP-UDL `udl` takes, header included.
+pub const fn user_data_octets(udl: usize) -> usize {
+    byte_count(udl)
+}
+
+const SINGLE_SEPTETS: usize = Budget::Single.septets();
+const CONCAT_SEPTETS: usize = Budget::Concat8.septets();
+
+// Walks `s` as concatenated parts, calling `split` with the byte offset at which each part after
+// the first begins, and returns the total number of septets.
+fn walk<F: FnMut(usize)>(s: &str, mut split: F) -> Result<usize, Gsm7Error> {
+    let mut septets = 0;
+    let mut in_segment = 0;
+    for (i, c) in s.char_indices() {
+        let len = lookup(c).septets().ok_or(Gsm7Error::Unencodable(c))?;
+        septets += len;
+        if in_segment + len > CONCAT_SEPTETS {
+            #[cfg(feature = "tracing")]
+            if in_segment < CONCAT_SEPTETS {
+                tracing::trace!(offset = i, "escape pair moved to the next segment");
+            }
+            split(i);
+            in_segment = 0;
+        }
+        in_segment += len;
+    }
+    Ok(septets)
+}
+
+/// Septets each character of `s` takes: 1 for the basic table, 2 for the extension table, or
+/// `None` if it has no GSM 7-bit encoding.
+pub fn costs(s: &str) -> impl Iterator<Item = (char, Option<u8>)> + '_ {
+    s.chars().map(|c| (c, lookup(c).septets().map(|n| n as u8)))
+}
+
+/// The longest prefix of `s` that fits in `max` septets, never splitting an escape pair. Fails if
+/// the prefix would stop at a character with no GSM 7-bit encoding.
+pub fn truncate_to_septets(s: &str, max: usize) -> Result<&str, Gsm7Error> {
+    let mut septets = 0;
+    for (i, c) in s.char_indices() {
+        septets += lookup(c).septets().ok_or(Gsm7Error::Unencodable(c))?;
+        if septets > max {
+            return Ok(&s[..i]);
+        }
+    }
+    Ok(s)
+}
+
+/// Number of SMS segments needed to send `s`, never splitting an escape pair across segments.
+pub fn segment_count(s: &str) -> Result<usize, Gsm7Error> {
+    #[cfg(feature = "nfc")]
+    let normalized = crate::transliterate::nfc(s);
+    #[cfg(feature = "nfc")]
+    let s = &*normalized;
+    let mut segments = 1;
+    let septets = walk(s, |_| segments += 1)?;
+    let segments = if septets <= SINGLE_SEPTETS { 1 } else { segments };
+    #[cfg(feature = "tracing")]
+    tracing::trace!(septets, segments, "segmented text");
+    Ok(segments)
+}
+
+/// Splits `s` into the parts it is sent as: itself if it fits in a single 160-septet SMS, and
+/// otherwise parts of at most 153 septets, leaving room for a concatenation header, with no escape
+/// pair split. There are always `segment_count(s)` parts.
+#[cfg(feature = "alloc")]
+pub fn split_for_sms(s: &str) -> Result<Vec<&str>, Gsm7Error> {
+    let mut starts = Vec::new();
+    let septets = walk(s, |i| starts.push(i))?;
+    if septets <= SINGLE_SEPTETS {
+        return Ok(alloc::vec![s]);
+    }
+    let mut parts = Vec::with_capacity(starts.len() + 1);
+    let mut rest = s;
+    for &start in starts.iter().rev() {
+        parts.push(&rest[start..]);
+        rest = &rest[..start];
+    }
+    parts.push(rest);
+    parts.reverse();
+    Ok(parts)
+}
@@ -0,0 +1,227 @@
+//! Serde helpers that store a string field as GSM 7-bit text, in one of three forms:
+//!
+//! - with `serde_packed` itself, as packed bytes after a byte giving their septet count, as the
+//!   TP-UDL and TP-UD of a PDU have them;
+//! - with `serde_packed::hex`, as the same bytes written as a string of hex digits;
+//! - with `serde_packed::text`, as the text itself, as `Gsm7String` is stored by default, but
+//!   checked to be encodable both ways.
+//!
+//! ```
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Message {
+//!     #[serde(with = "gsm7::serde_packed")]
+//!     packed: String,
+//!     #[serde(with = "gsm7::serde_packed::hex")]
+//!     hex: String,
+//!     #[serde(with = "gsm7::serde_packed::text")]
+//!     text: String,
+//! }
+//! ```
+//!
+//! The field may be any type that is `AsRef<str>` and `TryFrom<String>`, such as `String` or
+//! `Gsm7String`. The septet count lets the packed forms give back exactly the text stored, but
+//! limits it to 255 septets.
+
+use alloc::{string::String, vec::Vec};
+use core::convert::TryFrom;
+use core::fmt;
+
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::ser::{self, Serializer};
+
+use crate::{DefaultAlphabet, Gsm7Error, UserDataDecoder, UserDataEncoder};
+
+// The septet count of `s` and `s` packed, as a PDU has them.
+fn pack(s: &str) -> Result<Vec<u8>, Gsm7Error> {
+    let encoded = DefaultAlphabet.encode_user_data(s, None)?;
+    let udl = u8::try_from(encoded.udl).map_err(|_| Gsm7Error::WouldOverflow)?;
+    let mut bytes = Vec::with_capacity(1 + encoded.user_data.len());
+    bytes.push(udl);
+    bytes.extend_from_slice(&encoded.user_data);
+    Ok(bytes)
+}
+
+fn unpack(bytes: &[u8]) -> Result<String, Gsm7Error> {
+    let (&udl, packed) = bytes.split_first().ok_or(Gsm7Error::UnexpectedEnd)?;
+    Ok(DefaultAlphabet.decode_user_data(0x00, false, udl as usize, packed)?.text)
+}
+
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: AsRef<str> + ?Sized,
+    S: Serializer
+{
+    let bytes = pack(value.as_ref()).map_err(ser::Error::custom)?;
+    serializer.serialize_bytes(&bytes)
+}
+
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: TryFrom<String>,
+    T::Error: fmt::Display,
+    D: Deserializer<'de>
+{
+    let s = deserializer.deserialize_bytes(PackedVisitor)?;
+    T::try_from(s).map_err(de::Error::custom)
+}
+
+struct PackedVisitor;
+
+impl<'de> Visitor<'de> for PackedVisitor {
+    type Value = String;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a septet count and packed GSM 7-bit data")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        unpack(v).map_err(E::custom)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(b) = seq.next_element()? {
+            bytes.push(b);
+        }
+        self.visit_bytes(&bytes)
+    }
+}
+
+/// The same, but stored as a string of hex digits.
+pub mod hex {
+    use alloc::string::String;
+    use core::convert::TryFrom;
+    use core::fmt;
+
+    use serde::de::{self, Deserialize, Deserializer};
+    use serde::ser::{self, Serializer};
+
+    use super::{pack, unpack};
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: AsRef<str> + ?Sized,
+        S: Serializer
+    {
+        let bytes = pack(value.as_ref()).map_err(ser::Error::custom)?;
+        serializer.serialize_str(&crate::hex::to_hex(&bytes))
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: TryFrom<String>,
+        T::Error: fmt::Display,
+        D: Deserializer<'de>
+    {
+        // Owned, so that formats that can't lend out their input, such as JSON read from an
+        // `io::Read` or with escapes in the string, still work.
+        let digits = String::deserialize(deserializer)?;
+        let bytes = crate::hex::from_hex(&digits).ok_or_else(|| de::Error::custom("invalid hex"))?;
+        let s = unpack(&bytes).map_err(de::Error::custom)?;
+        T::try_from(s).map_err(de::Error::custom)
+    }
+}
+
+/// The same, but stored as the text itself, checked to be encodable.
+pub mod text {
+    use alloc::string::String;
+    use core::convert::TryFrom;
+    use core::fmt;
+
+    use serde::de::{self, Deserialize, Deserializer};
+    use serde::ser::{self, Serializer};
+
+    use crate::charset::{lookup, Code};
+    use crate::Gsm7Error;
+
+    fn check(s: &str) -> Result<(), Gsm7Error> {
+        match s.chars().find(|&c| lookup(c) == Code::Unencodable) {
+            Some(c) => Err(Gsm7Error::Unencodable(c)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: AsRef<str> + ?Sized,
+        S: Serializer
+    {
+        check(value.as_ref()).map_err(ser::Error::custom)?;
+        serializer.serialize_str(value.as_ref())
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: TryFrom<String>,
+        T::Error: fmt::Display,
+        D: Deserializer<'de>
+    {
+        let s = String::deserialize(deserializer)?;
+        check(&s).map_err(de::Error::custom)?;
+        T::try_from(s).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use serde::{Deserialize, Serialize};
+
+    use crate::Gsm7String;
+
+    #[derive(Serialize, Deserialize)]
+    struct Message {
+        #[serde(with = "crate::serde_packed")]
+        text: String,
+        #[serde(with = "crate::serde_packed::hex")]
+        hex: Gsm7String,
+    }
+
+    #[test]
+    fn round_trips_through_json() {
+        let message = Message { text: "Test".into(), hex: "Test".parse().unwrap() };
+        let json = serde_json::to_string(&message).unwrap();
+        assert_eq!(json, r#"{"text":[4,212,242,156,14],"hex":"04D4F29C0E"}"#);
+
+        let decoded: Message = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.text, "Test");
+        assert_eq!(decoded.hex.as_str(), "Test");
+
+        assert!(serde_json::from_str::<Message>(r#"{"text":[],"hex":"04D4F29C0E"}"#).is_err());
+        assert!(serde_json::from_str::<Message>(r#"{"text":[4,212,242,156,14],"hex":"04D4F"}"#).is_err());
+        assert!(serde_json::from_str::<Message>(r#"{"text":[5,212,242,156,14],"hex":"04D4F29C0E"}"#).is_err());
+        assert!(serde_json::to_string(&Message { text: "a".repeat(256), hex: message.hex }).is_err());
+    }
+
+    #[test]
+    fn round_trips_text_that_ends_in_the_spare_bits() {
+        // Seven septets leave seven spare bits, and fifteen leave them in the last byte too.
+        for text in ["1234567", "123456789ABCDEF", "123456@", "1234567@"] {
+            let message = Message { text: text.into(), hex: text.parse().unwrap() };
+            let decoded: Message = serde_json::from_str(&serde_json::to_string(&message).unwrap()).unwrap();
+            assert_eq!(decoded.text, text);
+            assert_eq!(decoded.hex.as_str(), text);
+        }
+    }
+
+    #[test]
+    fn hex_deserializes_from_a_reader() {
+        let json = r#"{"text":[4,212,242,156,14],"hex":"04D4F2\u0039C0E"}"#;
+        let decoded: Message = serde_json::from_reader(json.as_bytes()).unwrap();
+        assert_eq!(decoded.hex.as_str(), "Test");
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Text {
+        #[serde(with = "crate::serde_packed::text")]
+        text: String,
+    }
+
+    #[test]
+    fn text_is_checked_both_ways() {
+        let json = serde_json::to_string(&Text { text: "{Test}".into() }).unwrap();
+        assert_eq!(json, r#"{"text":"{Test}"}"#);
+        assert_eq!(serde_json::from_str::<Text>(&json).unwrap().text, "{Test}");
+        assert!(serde_json::to_string(&Text { text: "Tústico".into() }).is_err());
+        assert!(serde_json::from_str::<Text>(r#"{"text":"Tústico"}"#).is_err());
+    }
+}
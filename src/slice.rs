@@ -0,0 +1,425 @@
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+
+#[cfg(feature = "bytes")]
+use bytes::{Buf, BufMut};
+#[cfg(feature = "smallvec")]
+use smallvec::SmallVec;
+
+#[cfg(feature = "heapless")]
+use heapless::{String as HeaplessString, Vec as HeaplessVec};
+
+use crate::charset::{
+    basic_char, decode_next, extension_char, lookup, Code, ControlFilter, ControlPolicy, TruncatedEscapePolicy, ESC,
+};
+#[cfg(feature = "alloc")]
+use crate::charset::GSM7_UTF8;
+#[cfg(feature = "bytes")]
+use crate::pack::byte_count;
+use crate::pack::{septet_at, septet_count, Packer};
+use crate::error::trace_decode_error;
+use crate::Gsm7Error;
+
+/// Decodes packed GSM 7-bit data straight from a byte slice. Unlike `Gsm7Reader` this needs
+/// neither `std` nor an allocator.
+#[derive(Clone, Debug)]
+pub struct Chars<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    end: usize,
+    truncated_escape: TruncatedEscapePolicy,
+    control: ControlFilter,
+}
+
+impl<'a> Chars<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self::with_septet_range(bytes, 0, septet_count(bytes.len()))
+    }
+
+    /// Decodes exactly `udl` septets. Running out of input before then is an error rather than
+    /// the end of the text.
+    pub fn with_udl(bytes: &'a [u8], udl: usize) -> Self {
+        Self::with_septet_range(bytes, 0, udl)
+    }
+
+    // Decodes septets `start..end`, as when text follows a UDH.
+    pub(crate) fn with_septet_range(bytes: &'a [u8], start: usize, end: usize) -> Self {
+        Self { bytes, pos: start, end, truncated_escape: TruncatedEscapePolicy::Error, control: ControlFilter::default() }
+    }
+
+    /// Sets what to do when the input ends straight after an escape.
+    pub fn truncated_escape(self, policy: TruncatedEscapePolicy) -> Self {
+        Self { truncated_escape: policy, ..self }
+    }
+
+    /// Sets what to do with control characters other than those in `allowed`.
+    pub fn control_chars(self, policy: ControlPolicy, allowed: &str) -> Self {
+        Self { control: ControlFilter::new(policy, allowed), ..self }
+    }
+
+    /// Ends the text at the first septet 0x00 that starts a character, which (U)SIM Toolkit and
+    /// some older fields use as a terminator rather than as `@`.
+    pub fn nul_terminated(self) -> Self {
+        let mut i = self.pos;
+        while i < self.end {
+            match septet_at(self.bytes, i) {
+                Some(0) => return Self { end: i, ..self },
+                Some(ESC) => i += 2,
+                _ => i += 1,
+            }
+        }
+        self
+    }
+
+    /// The septet offset of the next character from the front.
+    pub fn offset(&self) -> usize {
+        self.pos
+    }
+
+    /// Yields each character along with the septet offset it starts at, which for an extension
+    /// character is that of its escape.
+    pub fn char_indices(self) -> CharIndices<'a> {
+        CharIndices { chars: self }
+    }
+
+    fn read_septet(&mut self) -> Option<Result<u8, Gsm7Error>> {
+        if self.pos == self.end {
+            return None;
+        }
+        let septet = septet_at(self.bytes, self.pos);
+        self.pos += 1;
+        Some(septet.ok_or(Gsm7Error::UnexpectedEnd))
+    }
+}
+
+impl<'a> Iterator for Chars<'a> {
+    type Item = Result<char, Gsm7Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.pos;
+        let policy = self.truncated_escape;
+        let c = decode_next(|| self.read_septet(), policy).map(|c| c.and_then(|c| self.control.apply(c)));
+        if let Some(Err(e)) = &c {
+            trace_decode_error(e, start);
+        }
+        c
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let septets = self.end - self.pos;
+        (septets.div_ceil(2), Some(septets))
+    }
+
+    // Skips characters a septet at a time, decoding only escapes and what the control filter
+    // rejects, so a character that `next` fails on fails here too, ending the skip.
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        for _ in 0..n {
+            let septet = if self.pos < self.end { septet_at(self.bytes, self.pos) } else { None };
+            match septet {
+                Some(septet) if septet != ESC && self.control.apply(basic_char(septet)).is_ok() => self.pos += 1,
+                _ => {
+                    if let Err(e) = self.next()? {
+                        return Some(Err(e));
+                    }
+                },
+            }
+        }
+        self.next()
+    }
+}
+
+/// The `n`th character of packed `bytes`, or of their first `udl` septets, found without
+/// decoding the characters before it or allocating.
+pub fn nth_char(bytes: &[u8], udl: Option<usize>, n: usize) -> Option<Result<char, Gsm7Error>> {
+    chars(bytes, udl).nth(n)
+}
+
+/// Whether packed `bytes`, or their first `udl` septets, decode to exactly `s`, decoding as it
+/// compares rather than allocating. Data that fails to decode equals nothing.
+pub fn packed_eq_str(bytes: &[u8], s: &str, udl: Option<usize>) -> bool {
+    chars(bytes, udl).map(Result::ok).eq(s.chars().map(Some))
+}
+
+/// Decodes packed `bytes`, or their first `udl` septets, appending the text as UTF-8 to `out`,
+/// which is left as it was on error. Looks up each septet's UTF-8 encoding directly, without
+/// going through `char`.
+#[cfg(feature = "alloc")]
+pub fn decode_to_vec(bytes: &[u8], udl: Option<usize>, out: &mut Vec<u8>) -> Result<(), Gsm7Error> {
+    let len = out.len();
+    let result = append_utf8(bytes, udl, out);
+    if result.is_err() {
+        out.truncate(len);
+    }
+    result
+}
+
+/// The same, appending to a `String`.
+#[cfg(feature = "alloc")]
+pub fn decode_to_string(bytes: &[u8], udl: Option<usize>, out: &mut String) -> Result<(), Gsm7Error> {
+    // SAFETY: `decode_to_vec` appends only whole UTF-8 encodings, and nothing at all on error.
+    decode_to_vec(bytes, udl, unsafe { out.as_mut_vec() })
+}
+
+#[cfg(feature = "alloc")]
+fn append_utf8(bytes: &[u8], udl: Option<usize>, out: &mut Vec<u8>) -> Result<(), Gsm7Error> {
+    let end = udl.unwrap_or_else(|| septet_count(bytes.len()));
+    out.reserve(end);
+    let mut i = 0;
+    while i < end {
+        let septet = septet_at(bytes, i).ok_or(Gsm7Error::UnexpectedEnd)?;
+        i += 1;
+        if septet == ESC {
+            if i == end {
+                return Err(Gsm7Error::TruncatedEscape);
+            }
+            let septet = septet_at(bytes, i).ok_or(Gsm7Error::UnexpectedEnd)?;
+            i += 1;
+            let c = extension_char(septet).ok_or(Gsm7Error::InvalidEscape(septet))?;
+            out.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+        }
+        else {
+            let (utf8, len) = &GSM7_UTF8[septet as usize];
+            out.extend_from_slice(&utf8[..*len as usize]);
+        }
+    }
+    Ok(())
+}
+
+/// Number of characters in packed `bytes`, or in their first `udl` septets, counting each escape
+/// pair once. Allocates nothing.
+pub fn char_count(bytes: &[u8], udl: Option<usize>) -> Result<usize, Gsm7Error> {
+    chars(bytes, udl).try_fold(0, |count, c| c.map(|_| count + 1))
+}
+
+/// Whether packed `bytes`, or their first `udl` septets, decode without error: every escape is
+/// followed by a septet in the extension table, and there are enough septets. Produces no output.
+pub fn is_valid_gsm7(bytes: &[u8], udl: Option<usize>) -> bool {
+    chars(bytes, udl).all(|c| c.is_ok())
+}
+
+/// The index, in characters, of the first occurrence of `needle` in packed `haystack`, decoding
+/// incrementally and allocating nothing. Characters that fail to decode match nothing.
+pub fn find_in_packed(haystack: &[u8], needle: &str) -> Option<usize> {
+    let mut chars = Chars::new(haystack);
+    let mut index = 0;
+    loop {
+        let mut candidate = chars.clone();
+        if needle.chars().all(|c| candidate.next() == Some(Ok(c))) {
+            return Some(index);
+        }
+        // An undecodable character is stepped over like any other.
+        let _ = chars.next()?;
+        index += 1;
+    }
+}
+
+fn chars(bytes: &[u8], udl: Option<usize>) -> Chars<'_> {
+    match udl {
+        Some(udl) => Chars::with_udl(bytes, udl),
+        None => Chars::new(bytes),
+    }
+}
+
+/// Decodes from the end. Whether the last septet completes an escape pair depends on whether an
+/// odd number of escapes comes before it, so a long run of escapes is scanned in full.
+impl<'a> DoubleEndedIterator for Chars<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.pos == self.end {
+            return None;
+        }
+        let last = match septet_at(self.bytes, self.end - 1) {
+            Some(septet) => septet,
+            None => {
+                // A UDL longer than the input; nothing more can be decoded from this end.
+                self.end = self.pos;
+                return Some(Err(Gsm7Error::UnexpectedEnd));
+            },
+        };
+        let escapes = (self.pos..self.end - 1).rev().take_while(|&i| septet_at(self.bytes, i) == Some(ESC)).count();
+        let c = if escapes % 2 == 1 {
+            self.end -= 2;
+            extension_char(last).ok_or(Gsm7Error::InvalidEscape(last))
+        }
+        else {
+            self.end -= 1;
+            match (last, self.truncated_escape) {
+                (ESC, TruncatedEscapePolicy::Space) => Ok(' '),
+                (ESC, TruncatedEscapePolicy::Error) => Err(Gsm7Error::TruncatedEscape),
+                _ => Ok(basic_char(last)),
+            }
+        };
+        let c = c.and_then(|c| self.control.apply(c));
+        if let Err(e) = &c {
+            trace_decode_error(e, self.end);
+        }
+        Some(c)
+    }
+}
+
+/// The characters of packed data and their septet offsets, from `Chars::char_indices`.
+#[derive(Clone, Debug)]
+pub struct CharIndices<'a> {
+    chars: Chars<'a>,
+}
+
+impl<'a> CharIndices<'a> {
+    /// The septet offset of the next character from the front.
+    pub fn offset(&self) -> usize {
+        self.chars.pos
+    }
+}
+
+impl<'a> Iterator for CharIndices<'a> {
+    type Item = Result<(usize, char), Gsm7Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let offset = self.chars.pos;
+        Some(self.chars.next()?.map(|c| (offset, c)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.chars.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for CharIndices<'a> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let c = self.chars.next_back()?;
+        Some(c.map(|c| (self.chars.end, c)))
+    }
+}
+
+/// Encodes `s` into `out`, returning the number of bytes written.
+pub fn encode_into(s: &str, out: &mut [u8]) -> Result<usize, Gsm7Error> {
+    let mut len = 0;
+    encode_with(s, |b| {
+        *out.get_mut(len).ok_or(Gsm7Error::BufferTooSmall)? = b;
+        len += 1;
+        Ok(())
+    })?;
+    Ok(len)
+}
+
+/// Decodes `bytes` into `out` as UTF-8, returning the number of bytes written.
+pub fn decode_into(bytes: &[u8], out: &mut [u8]) -> Result<usize, Gsm7Error> {
+    let mut len = 0;
+    for c in Chars::new(bytes) {
+        let c = c?;
+        let buf = out.get_mut(len..len + c.len_utf8()).ok_or(Gsm7Error::BufferTooSmall)?;
+        len += c.encode_utf8(buf).len();
+    }
+    Ok(len)
+}
+
+/// Encodes `s` into a fixed-capacity `heapless::Vec`.
+#[cfg(feature = "heapless")]
+pub fn encode_heapless<const N: usize>(s: &str) -> Result<HeaplessVec<u8, N>, Gsm7Error> {
+    let mut out = HeaplessVec::new();
+    encode_with(s, |b| out.push(b).map_err(|_| Gsm7Error::BufferTooSmall))?;
+    Ok(out)
+}
+
+/// Decodes `bytes` into a fixed-capacity `heapless::String`.
+#[cfg(feature = "heapless")]
+pub fn decode_heapless<const N: usize>(bytes: &[u8]) -> Result<HeaplessString<N>, Gsm7Error> {
+    let mut out = HeaplessString::new();
+    for c in Chars::new(bytes) {
+        out.push(c?).map_err(|_| Gsm7Error::BufferTooSmall)?;
+    }
+    Ok(out)
+}
+
+/// Encodes `s` in one go.
+#[cfg(feature = "alloc")]
+pub fn encode(s: &str) -> Result<Vec<u8>, Gsm7Error> {
+    let mut out = Vec::with_capacity(s.len());
+    encode_with(s, |b| {
+        out.push(b);
+        Ok(())
+    })?;
+    Ok(out)
+}
+
+/// Encodes `s` into a buffer that lives on the stack unless the output exceeds 140 bytes, the
+/// size of a single SMS.
+#[cfg(feature = "smallvec")]
+pub fn encode_small(s: &str) -> Result<SmallVec<[u8; 140]>, Gsm7Error> {
+    let mut out = SmallVec::new();
+    encode_with(s, |b| {
+        out.push(b);
+        Ok(())
+    })?;
+    Ok(out)
+}
+
+/// Encodes `s` straight into `buf`, returning the number of bytes written.
+#[cfg(feature = "bytes")]
+pub fn encode_buf<B: BufMut>(s: &str, buf: &mut B) -> Result<usize, Gsm7Error> {
+    let mut len = 0;
+    encode_with(s, |b| {
+        if !buf.has_remaining_mut() {
+            return Err(Gsm7Error::BufferTooSmall);
+        }
+        buf.put_u8(b);
+        len += 1;
+        Ok(())
+    })?;
+    Ok(len)
+}
+
+/// Decodes exactly `udl` septets from the front of `buf`, consuming the bytes that hold them even
+/// if they fail to decode. If `buf` is too short, nothing is consumed.
+#[cfg(feature = "bytes")]
+pub fn decode_buf<B: Buf>(buf: &mut B, udl: usize) -> Result<String, Gsm7Error> {
+    let len = byte_count(udl);
+    if buf.remaining() < len {
+        return Err(Gsm7Error::UnexpectedEnd);
+    }
+    if buf.chunk().len() >= len {
+        let s = Chars::with_udl(&buf.chunk()[..len], udl).collect();
+        buf.advance(len);
+        s
+    }
+    else {
+        // Only copies when the bytes are split across chunks.
+        Chars::with_udl(&buf.copy_to_bytes(len), udl).collect()
+    }
+}
+
+/// Decodes `bytes` in one go.
+#[cfg(feature = "alloc")]
+pub fn decode(bytes: &[u8]) -> Result<String, Gsm7Error> {
+    Chars::new(bytes).collect()
+}
+
+fn encode_with<F>(s: &str, mut emit: F) -> Result<(), Gsm7Error>
+where
+    F: FnMut(u8) -> Result<(), Gsm7Error>
+{
+    #[cfg(feature = "nfc")]
+    let normalized = crate::transliterate::nfc(s);
+    #[cfg(feature = "nfc")]
+    let s = &*normalized;
+    let mut packer = Packer::default();
+    for c in s.chars() {
+        match lookup(c) {
+            Code::Basic(septet) => {
+                if let Some(b) = packer.push(septet) {
+                    emit(b)?;
+                }
+            },
+            Code::Escaped(septet) => {
+                for &septet in &[ESC, septet] {
+                    if let Some(b) = packer.push(septet) {
+                        emit(b)?;
+                    }
+                }
+            },
+            Code::Unencodable => return Err(Gsm7Error::Unencodable(c)),
+        }
+    }
+    if let Some(b) = packer.finish() {
+        emit(b)?;
+    }
+    Ok(())
+}
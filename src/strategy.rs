@@ -0,0 +1,112 @@
+//! Proptest strategies for generating GSM 7-bit text, packed data and user data headers.
+
+use std::string::String;
+use std::vec::Vec;
+
+use proptest::collection::{vec, SizeRange};
+use proptest::prelude::*;
+use proptest::sample::select;
+
+use crate::charset::{chars_where, Code};
+use crate::{encode, Gsm7String};
+
+/// Any character in the basic table.
+pub fn basic_char() -> impl Strategy<Value = char> {
+    select(chars_where(|code| matches!(code, Code::Basic(_))))
+}
+
+/// Any character in the extension table, each of which takes two septets.
+pub fn extension_char() -> impl Strategy<Value = char> {
+    select(chars_where(|code| matches!(code, Code::Escaped(_))))
+}
+
+/// Any encodable character, with extension characters made more likely than they would be if
+/// chosen uniformly.
+pub fn gsm7_char() -> impl Strategy<Value = char> {
+    prop_oneof![3 => basic_char(), 1 => extension_char()]
+}
+
+/// Any encodable string whose length in characters is within `len`.
+pub fn gsm7_string(len: impl Into<SizeRange>) -> impl Strategy<Value = String> {
+    vec(gsm7_char(), len).prop_map(|chars| chars.into_iter().collect())
+}
+
+/// Packed data together with its UDL (length in septets), for text whose length in characters is
+/// within `len`. Lengths cover every padding case.
+pub fn packed(len: impl Into<SizeRange>) -> impl Strategy<Value = (Vec<u8>, usize)> {
+    gsm7_string(len).prop_map(|s| {
+        let udl = s.parse::<Gsm7String>().map(|s| s.septet_len()).unwrap_or(0);
+        (encode(&s).unwrap_or_default(), udl)
+    })
+}
+
+// An 8-bit or 16-bit reference concatenation element, whose part number is within the total.
+fn concat_element() -> impl Strategy<Value = Vec<u8>> {
+    let parts = (1..=255u8).prop_flat_map(|total| (Just(total), 1..=total));
+    (any::<bool>(), any::<u16>(), parts).prop_map(|(short, reference, (total, seq))| {
+        let [high, low] = reference.to_be_bytes();
+        if short {
+            vec![0x00, 0x03, low, total, seq]
+        }
+        else {
+            vec![0x08, 0x04, high, low, total, seq]
+        }
+    })
+}
+
+/// A user data header: from one to four information elements, each of them concatenation with
+/// an 8-bit or 16-bit reference, port addressing with 8-bit or 16-bit ports, or a national
+/// language single or locking shift, after the UDHL that gives their length.
+pub fn udh() -> impl Strategy<Value = Vec<u8>> {
+    let element = prop_oneof![
+        concat_element(),
+        any::<[u8; 2]>().prop_map(|[dest, orig]| vec![0x04, 0x02, dest, orig]),
+        any::<[u8; 4]>().prop_map(|ports| [&[0x05, 0x04][..], &ports].concat()),
+        (select(&[0x24u8, 0x25][..]), 1..=3u8).prop_map(|(iei, language)| vec![iei, 0x01, language]),
+    ];
+    vec(element, 1..=4).prop_map(|elements| {
+        let elements = elements.concat();
+        [&[elements.len() as u8][..], &elements].concat()
+    })
+}
+
+impl Arbitrary for Gsm7String {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        gsm7_string(0..200).prop_filter_map("unencodable", |s| s.parse().ok()).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use crate::{Chars, Gsm7String};
+
+    proptest! {
+        #[test]
+        fn packed_round_trips((packed, udl) in super::packed(0..50)) {
+            let s = Chars::with_udl(&packed, udl).collect::<Result<String, _>>().unwrap();
+            prop_assert_eq!(s.parse::<Gsm7String>().unwrap().septet_len(), udl);
+            prop_assert_eq!(crate::encode(&s).unwrap(), packed);
+        }
+
+        #[test]
+        fn udh_round_trips(udh in super::udh(), s in super::gsm7_string(0..20)) {
+            use crate::{DefaultAlphabet, UserDataDecoder, UserDataEncoder};
+
+            let encoded = DefaultAlphabet.encode_user_data(&s, Some(&udh[1..])).unwrap();
+            prop_assert!(encoded.user_data.starts_with(&udh));
+            let decoded = DefaultAlphabet.decode_user_data(0x00, true, encoded.udl, &encoded.user_data).unwrap();
+            prop_assert_eq!(decoded.udh.as_deref(), Some(&udh[1..]));
+            prop_assert_eq!(decoded.text, s);
+        }
+
+        #[test]
+        fn any_gsm7_string_encodes(s in any::<Gsm7String>()) {
+            prop_assert!(crate::encode(s.as_str()).is_ok());
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::vec::Vec;
+
+use futures::io::{AsyncRead, AsyncWrite};
+use futures::{ready, Sink, Stream};
+
+use crate::pack::{Packer, SeptetBuf};
+
+const BUF_LEN: usize = 1024;
+
+/// `Stream` of characters decoded from packed GSM 7-bit data read from a futures `AsyncRead`.
+pub struct Gsm7Stream<R> {
+    reader: R,
+    septets: SeptetBuf,
+}
+
+impl<R: AsyncRead + Unpin> Gsm7Stream<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, septets: SeptetBuf::default() }
+    }
+
+    /// Creates a stream that decodes exactly `udl` septets, never reading past the bytes that
+    /// hold them.
+    pub fn with_udl(reader: R, udl: usize) -> Self {
+        Self { reader, septets: SeptetBuf::with_udl(udl) }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for Gsm7Stream<R> {
+    type Item = io::Result<char>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        while this.septets.needs_input() {
+            let mut buf = [0; BUF_LEN];
+            let len = this.septets.read_len(BUF_LEN);
+            let len = if len == 0 { 0 } else { ready!(Pin::new(&mut this.reader).poll_read(cx, &mut buf[..len]))? };
+            this.septets.push(&buf[..len]);
+        }
+        Poll::Ready(this.septets.decode())
+    }
+}
+
+/// `Sink` of characters, packed as GSM 7-bit data and written to a futures `AsyncWrite`. Closing
+/// the sink writes the final partial byte, padded as `Gsm7Writer::into_writer` pads it.
+pub struct Gsm7Sink<W> {
+    writer: W,
+    packer: Packer,
+    buf: Vec<u8>,
+    written: usize,
+    finished: bool,
+}
+
+impl<W: AsyncWrite + Unpin> Gsm7Sink<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer, packer: Packer::default(), buf: Vec::with_capacity(BUF_LEN), written: 0, finished: false }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn poll_write_buf(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while self.written < self.buf.len() {
+            let len = ready!(Pin::new(&mut self.writer).poll_write(cx, &self.buf[self.written..]))?;
+            if len == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            self.written += len;
+        }
+        self.buf.clear();
+        self.written = 0;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> Sink<char> for Gsm7Sink<W> {
+    type Error = io::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if this.buf.len() >= BUF_LEN {
+            ready!(this.poll_write_buf(cx))?;
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, c: char) -> io::Result<()> {
+        let this = self.get_mut();
+        Ok(this.packer.push_char(c, &mut this.buf)?)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_write_buf(cx))?;
+        Pin::new(&mut this.writer).poll_flush(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        if !this.finished {
+            this.buf.extend(this.packer.finish());
+            this.finished = true;
+        }
+        ready!(this.poll_write_buf(cx))?;
+        Pin::new(&mut this.writer).poll_close(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+    use futures::io::Cursor;
+    use futures::{stream, SinkExt, StreamExt, TryStreamExt};
+
+    use super::{Gsm7Sink, Gsm7Stream};
+
+    #[test]
+    fn stream_and_sink_round_trip() {
+        block_on(async {
+            let text = "{Hello} €5 ".repeat(200);
+            let mut sink = Gsm7Sink::new(Cursor::new(Vec::new()));
+            sink.send_all(&mut stream::iter(text.chars().map(Ok))).await.unwrap();
+            sink.close().await.unwrap();
+            let packed = sink.into_inner().into_inner();
+            assert_eq!(packed, crate::encode(&text).unwrap());
+
+            let s: String = Gsm7Stream::new(&packed[..]).try_collect().await.unwrap();
+            assert_eq!(s.trim_end_matches('\r'), text);
+
+            let chars: Vec<_> = Gsm7Stream::with_udl(&packed[..], 7).map(Result::unwrap).collect().await;
+            assert_eq!(chars, ['{', 'H', 'e', 'l', 'l', 'o']);
+        });
+    }
+}
@@ -0,0 +1,240 @@
+use alloc::string::String;
+use core::borrow::Borrow;
+use core::convert::TryFrom;
+use core::hash::{Hash, Hasher};
+use core::ops::Deref;
+use core::str::FromStr;
+use core::{cmp, fmt};
+
+use crate::charset::lookup;
+#[cfg(feature = "arbitrary")]
+use crate::charset::GSM7_BY_CHAR;
+#[cfg(feature = "quickcheck")]
+use crate::charset::{chars_where, Code};
+use crate::Gsm7Error;
+
+/// A string that is known to be encodable in GSM 7-bit, along with its encoded length.
+///
+/// With the `serde` feature it is stored as its text. The `serde_packed` helpers store it as
+/// packed bytes or hex instead.
+#[derive(Clone, Debug, Default)]
+pub struct Gsm7String {
+    s: String,
+    septets: usize,
+}
+
+impl Gsm7String {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.s
+    }
+
+    /// Length of the string when encoded, in septets.
+    pub fn septet_len(&self) -> usize {
+        self.septets
+    }
+
+    pub fn into_string(self) -> String {
+        self.s
+    }
+
+    /// Appends `c`, failing and leaving the string as it was if it has no GSM 7-bit encoding.
+    pub fn push(&mut self, c: char) -> Result<(), Gsm7Error> {
+        self.septets += lookup(c).septets().ok_or(Gsm7Error::Unencodable(c))?;
+        self.s.push(c);
+        Ok(())
+    }
+
+    /// Appends `s`, failing and leaving the string as it was if any of it has no GSM 7-bit
+    /// encoding.
+    pub fn push_str(&mut self, s: &str) -> Result<(), Gsm7Error> {
+        self.septets += septet_len(s)?;
+        self.s.push_str(s);
+        Ok(())
+    }
+
+    /// Shortens the string to at most `max` septets, dropping whole characters from the end.
+    pub fn truncate_septets(&mut self, max: usize) {
+        while self.septets > max {
+            match self.s.pop() {
+                Some(c) => self.septets = self.septets.saturating_sub(lookup(c).septets().unwrap_or(0)),
+                // The count can only be out of sync through a bug, and an empty string has none.
+                None => self.septets = 0,
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.s.clear();
+        self.septets = 0;
+    }
+}
+
+fn septet_len(s: &str) -> Result<usize, Gsm7Error> {
+    s.chars().try_fold(0, |len, c| {
+        Ok(len + lookup(c).septets().ok_or(Gsm7Error::Unencodable(c))?)
+    })
+}
+
+impl TryFrom<String> for Gsm7String {
+    type Error = Gsm7Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        let septets = septet_len(&s)?;
+        Ok(Self { s, septets })
+    }
+}
+
+impl TryFrom<&str> for Gsm7String {
+    type Error = Gsm7Error;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        let septets = septet_len(s)?;
+        Ok(Self { s: s.into(), septets })
+    }
+}
+
+impl FromStr for Gsm7String {
+    type Err = Gsm7Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::try_from(s)
+    }
+}
+
+impl AsRef<str> for Gsm7String {
+    fn as_ref(&self) -> &str {
+        &self.s
+    }
+}
+
+impl Deref for Gsm7String {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.s
+    }
+}
+
+// Compared and hashed as the text alone, as `str` is, so that `Borrow<str>` can look it up by `&str`.
+impl Borrow<str> for Gsm7String {
+    fn borrow(&self) -> &str {
+        &self.s
+    }
+}
+
+impl PartialEq for Gsm7String {
+    fn eq(&self, other: &Self) -> bool {
+        self.s == other.s
+    }
+}
+
+impl Eq for Gsm7String {}
+
+impl PartialEq<str> for Gsm7String {
+    fn eq(&self, other: &str) -> bool {
+        self.s == other
+    }
+}
+
+impl PartialEq<&str> for Gsm7String {
+    fn eq(&self, other: &&str) -> bool {
+        self.s == *other
+    }
+}
+
+impl PartialOrd for Gsm7String {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Gsm7String {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        self.s.cmp(&other.s)
+    }
+}
+
+impl Hash for Gsm7String {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.s.hash(state)
+    }
+}
+
+impl fmt::Display for Gsm7String {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.s, f)
+    }
+}
+
+impl From<Gsm7String> for String {
+    fn from(s: Gsm7String) -> Self {
+        s.s
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Gsm7String {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "{=str}", self.as_str())
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for Gsm7String {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut s = Self::new();
+        for _ in 0..u.arbitrary_len::<char>()? {
+            let &(c, code) = u.choose(&GSM7_BY_CHAR)?;
+            s.s.push(c);
+            s.septets += code.septets().unwrap_or(0);
+        }
+        Ok(s)
+    }
+}
+
+// Generates text with extension characters made more likely than they would be if chosen
+// uniformly, as the proptest strategies do.
+#[cfg(feature = "quickcheck")]
+impl quickcheck::Arbitrary for Gsm7String {
+    fn arbitrary(g: &mut quickcheck::Gen) -> Self {
+        let basic = chars_where(|code| matches!(code, Code::Basic(_)));
+        let extension = chars_where(|code| matches!(code, Code::Escaped(_)));
+        let mut s = Self::new();
+        for _ in 0..usize::arbitrary(g) % (g.size() + 1) {
+            let (chars, septets) = if u8::arbitrary(g) % 4 == 0 {
+                (&extension, 2)
+            }
+            else {
+                (&basic, 1)
+            };
+            if let Some(&c) = g.choose(chars) {
+                s.s.push(c);
+                s.septets += septets;
+            }
+        }
+        s
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        Box::new(self.s.shrink().filter_map(|s| Self::try_from(s).ok()))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Gsm7String {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.s)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Gsm7String {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Self::try_from(s).map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,133 @@
+use std::io;
+use std::vec::Vec;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::pack::{Packer, SeptetBuf};
+
+const BUF_LEN: usize = 1024;
+
+/// Asynchronous decoder for packed GSM 7-bit data from a Tokio `AsyncRead`.
+pub struct AsyncGsm7Reader<R> {
+    reader: R,
+    septets: SeptetBuf,
+}
+
+impl<R: AsyncRead + Unpin> AsyncGsm7Reader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, septets: SeptetBuf::default() }
+    }
+
+    /// Creates a reader that decodes exactly `udl` septets, never reading past the bytes that
+    /// hold them.
+    pub fn with_udl(reader: R, udl: usize) -> Self {
+        Self { reader, septets: SeptetBuf::with_udl(udl) }
+    }
+
+    /// Reads the next character, or `None` at the end of the input.
+    pub async fn read_char(&mut self) -> io::Result<Option<char>> {
+        while self.septets.needs_input() {
+            let mut buf = [0; BUF_LEN];
+            let len = self.septets.read_len(BUF_LEN);
+            let len = if len == 0 { 0 } else { self.reader.read(&mut buf[..len]).await? };
+            self.septets.push(&buf[..len]);
+        }
+        self.septets.decode().transpose()
+    }
+
+    /// Reads all remaining characters into `buf`, returning the number read.
+    pub async fn read_to_string(&mut self, buf: &mut String) -> io::Result<usize> {
+        let mut count = 0;
+        while let Some(c) = self.read_char().await? {
+            buf.push(c);
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// Asynchronous encoder writing packed GSM 7-bit data to a Tokio `AsyncWrite`. Output is
+/// buffered until `flush` or `into_writer` is called, or the buffer fills.
+pub struct AsyncGsm7Writer<W> {
+    writer: W,
+    packer: Packer,
+    buf: Vec<u8>,
+}
+
+impl<W: AsyncWrite + Unpin> AsyncGsm7Writer<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer, packer: Packer::default(), buf: Vec::with_capacity(BUF_LEN) }
+    }
+
+    pub async fn write_char(&mut self, c: char) -> io::Result<()> {
+        self.packer.push_char(c, &mut self.buf)?;
+        if self.buf.len() >= BUF_LEN {
+            self.write_buf().await?;
+        }
+        Ok(())
+    }
+
+    pub async fn write_str(&mut self, s: &str) -> io::Result<()> {
+        for c in s.chars() {
+            self.write_char(c).await?;
+        }
+        Ok(())
+    }
+
+    /// Writes out all complete bytes and flushes the underlying writer.
+    pub async fn flush(&mut self) -> io::Result<()> {
+        self.write_buf().await?;
+        self.writer.flush().await
+    }
+
+    /// Writes the final partial byte, padded as `Gsm7Writer::into_writer` pads it, flushes, and
+    /// returns the underlying writer.
+    pub async fn into_writer(mut self) -> io::Result<W> {
+        self.buf.extend(self.packer.finish());
+        self.flush().await?;
+        Ok(self.writer)
+    }
+
+    async fn write_buf(&mut self) -> io::Result<()> {
+        self.writer.write_all(&self.buf).await?;
+        self.buf.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::{AsyncGsm7Reader, AsyncGsm7Writer};
+
+    #[tokio::test]
+    async fn async_round_trip() {
+        let text = "{Hello} €5 ".repeat(200);
+        let mut writer = AsyncGsm7Writer::new(Vec::new());
+        writer.write_str(&text).await.unwrap();
+        let packed = writer.into_writer().await.unwrap();
+        assert_eq!(packed, crate::encode(&text).unwrap());
+
+        let mut s = String::new();
+        AsyncGsm7Reader::new(&packed[..]).read_to_string(&mut s).await.unwrap();
+        assert_eq!(s.trim_end_matches('\r'), text);
+
+        // With a UDL, nothing after the user data is consumed.
+        let mut input = crate::encode("{Hello}").unwrap();
+        input.extend_from_slice(b"rest");
+        let mut reader = AsyncGsm7Reader::with_udl(&input[..], 9);
+        let mut s = String::new();
+        reader.read_to_string(&mut s).await.unwrap();
+        assert_eq!(s, "{Hello}");
+        assert_eq!(reader.into_inner(), b"rest");
+
+        let mut reader = AsyncGsm7Reader::with_udl(&input[..2], 9);
+        let e = reader.read_to_string(&mut String::new()).await.unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
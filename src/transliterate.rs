@@ -0,0 +1,257 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+#[cfg(feature = "downgrade")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "nfc")]
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+#[cfg(feature = "downgrade")]
+use crate::charset::{lookup, Code};
+
+// Typographic characters and their GSM 7-bit equivalents. An empty replacement drops the
+// character.
+fn punctuation(c: char) -> Option<&'static str> {
+    Some(match c {
+        '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' => "'",
+        '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' | '«' | '»' => "\"",
+        '\u{2010}' | '\u{2011}' | '\u{2012}' | '\u{2013}' | '\u{2014}' | '\u{2015}' | '\u{2212}' => "-",
+        '\u{2026}' => "...",
+        '\u{00A0}' | '\u{2002}'..='\u{200A}' | '\u{202F}' | '\u{205F}' => " ",
+        '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}' | '\u{00AD}' => "",
+        '\u{2028}' | '\u{2029}' => "\n",
+        '\u{2022}' => "-",
+        '\u{02C6}' => "^",
+        '\u{02DC}' => "~",
+        _ => return None,
+    })
+}
+
+/// Replaces curly quotes, dashes, the ellipsis, non-breaking and other typographic spaces, and
+/// invisible characters such as zero-width spaces and soft hyphens with GSM 7-bit equivalents,
+/// so that text pasted from a word processor doesn't force UCS-2. Other characters are left
+/// alone, and the input is returned as is if nothing needed replacing.
+pub fn normalize_punctuation(s: &str) -> Cow<'_, str> {
+    let first = match s.char_indices().find(|&(_, c)| punctuation(c).is_some()) {
+        Some((i, _)) => i,
+        None => return Cow::Borrowed(s),
+    };
+    let mut out = String::with_capacity(s.len());
+    out.push_str(&s[..first]);
+    for c in s[first..].chars() {
+        match punctuation(c) {
+            Some(replacement) => out.push_str(replacement),
+            None => out.push(c),
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// How to write line breaks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    /// Leave them as they are.
+    #[default]
+    Keep,
+    /// Make every `\r\n` and `\r` a `\n`.
+    Lf,
+    /// Make every `\r\n` and `\n` a `\r`.
+    Cr,
+    /// Make every `\n` and `\r` a `\r\n`.
+    CrLf,
+}
+
+impl LineEnding {
+    fn line_break(self) -> &'static str {
+        match self {
+            LineEnding::Keep => "",
+            LineEnding::Lf => "\n",
+            LineEnding::Cr => "\r",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+// Tracks whether the last character was a CR, so that the LF of a CRLF split across calls is
+// still recognised.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct LineEndings {
+    pub(crate) ending: LineEnding,
+    after_cr: bool,
+}
+
+impl LineEndings {
+    pub(crate) fn new(ending: LineEnding) -> Self {
+        Self { ending, after_cr: false }
+    }
+
+    // Notes that characters other than line breaks were written without going through `convert`.
+    #[cfg(feature = "std")]
+    pub(crate) fn not_a_line_break(&mut self) {
+        self.after_cr = false;
+    }
+
+    // What to write in place of `c`, or `None` to write it as it is.
+    pub(crate) fn convert(&mut self, c: char) -> Option<&'static str> {
+        if self.ending == LineEnding::Keep {
+            return None;
+        }
+        let after_cr = core::mem::replace(&mut self.after_cr, c == '\r');
+        match c {
+            '\r' => Some(self.ending.line_break()),
+            '\n' if after_cr => Some(""),
+            '\n' => Some(self.ending.line_break()),
+            _ => None,
+        }
+    }
+}
+
+/// Writes every line break in `s` as `ending` says, returning it as is if nothing changes.
+pub fn normalize_line_endings(s: &str, ending: LineEnding) -> Cow<'_, str> {
+    if ending == LineEnding::Keep || !s.contains(['\r', '\n']) {
+        return Cow::Borrowed(s);
+    }
+    let mut line_endings = LineEndings::new(ending);
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match line_endings.convert(c) {
+            Some(replacement) => out.push_str(replacement),
+            None => out.push(c),
+        }
+    }
+    if out == s {
+        Cow::Borrowed(s)
+    }
+    else {
+        Cow::Owned(out)
+    }
+}
+
+// Greek capitals, and symbols drawn like them, mapped to the Greek or Latin glyph in the basic
+// table that looks the same.
+fn greek(c: char) -> Option<char> {
+    Some(match c {
+        '\u{0391}' | '\u{0386}' => 'A',
+        '\u{0392}' => 'B',
+        '\u{0395}' | '\u{0388}' => 'E',
+        '\u{0396}' => 'Z',
+        '\u{0397}' | '\u{0389}' => 'H',
+        '\u{0399}' | '\u{038A}' | '\u{03AA}' => 'I',
+        '\u{039A}' => 'K',
+        '\u{039C}' => 'M',
+        '\u{039D}' => 'N',
+        '\u{039F}' | '\u{038C}' => 'O',
+        '\u{03A1}' => 'P',
+        '\u{03A4}' => 'T',
+        '\u{03A5}' | '\u{038E}' | '\u{03AB}' => 'Y',
+        '\u{03A7}' => 'X',
+        '\u{038F}' | '\u{2126}' => 'Ω',
+        '\u{2206}' => 'Δ',
+        '\u{2211}' => 'Σ',
+        _ => return None,
+    })
+}
+
+/// Replaces Greek capitals that the basic table lacks, such as `Α` and `Ή`, with the Latin
+/// capitals drawn the same, and the ohm, increment and n-ary sum signs with `Ω`, `Δ` and `Σ`, as
+/// Greek SMS has always been written. Lowercase Greek is left alone and so still fails to encode;
+/// uppercase the text first to send it.
+pub fn map_greek(s: &str) -> Cow<'_, str> {
+    if !s.chars().any(|c| greek(c).is_some()) {
+        return Cow::Borrowed(s);
+    }
+    Cow::Owned(s.chars().map(|c| greek(c).unwrap_or(c)).collect())
+}
+
+// Characters drawn like a character GSM 7-bit has, and that character.
+fn homoglyph(c: char) -> Option<char> {
+    let ascii = match c {
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0)?,
+        '\u{3000}' | '\u{00A0}' => ' ',
+        '\u{2212}' => '-',
+        '\u{2215}' | '\u{2044}' => '/',
+        '\u{02BC}' => '\'',
+        'А' => 'A',
+        'В' => 'B',
+        'Е' => 'E',
+        'К' => 'K',
+        'М' => 'M',
+        'Н' => 'H',
+        'О' => 'O',
+        'Р' => 'P',
+        'С' => 'C',
+        'Т' => 'T',
+        'Х' => 'X',
+        'І' => 'I',
+        'Ј' => 'J',
+        'Ѕ' => 'S',
+        'а' => 'a',
+        'е' => 'e',
+        'о' => 'o',
+        'р' => 'p',
+        'с' => 'c',
+        'у' => 'y',
+        'х' => 'x',
+        'і' => 'i',
+        'ј' => 'j',
+        'ѕ' => 's',
+        _ => return None,
+    };
+    Some(ascii).filter(|&ascii| crate::is_gsm7_char(ascii))
+}
+
+/// Replaces characters that are drawn like ones GSM 7-bit has with those: full-width ASCII, the
+/// ideographic and non-breaking spaces, the minus sign, the division and fraction slashes, and
+/// the Cyrillic letters that look Latin. Text pasted from other scripts' keyboards or from the
+/// web often has them and so would need UCS-2 for no visible reason. Returns the input as is if
+/// nothing needed replacing.
+pub fn normalize_homoglyphs(s: &str) -> Cow<'_, str> {
+    if !s.chars().any(|c| homoglyph(c).is_some()) {
+        return Cow::Borrowed(s);
+    }
+    Cow::Owned(s.chars().map(|c| homoglyph(c).unwrap_or(c)).collect())
+}
+
+// Composes `s` to NFC, so that a letter followed by a combining accent encodes as the letter
+// with the accent when the table has it. Text that is already NFC, which is nearly all of it, is
+// returned as is after a quick check.
+#[cfg(feature = "nfc")]
+pub(crate) fn nfc(s: &str) -> Cow<'_, str> {
+    match is_nfc_quick(s.chars()) {
+        IsNormalized::Yes => Cow::Borrowed(s),
+        _ => Cow::Owned(s.nfc().collect()),
+    }
+}
+
+/// A character `downgrade` replaced, at a byte offset into its input.
+#[cfg(feature = "downgrade")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Replacement {
+    pub offset: usize,
+    pub original: char,
+    /// The text that replaced it, which may be empty.
+    pub replacement: &'static str,
+}
+
+/// Replaces every character with no GSM 7-bit encoding with the nearest text that has one, going
+/// by `normalize_punctuation` and then the transliterations of the `deunicode` crate, so that the
+/// result never needs UCS-2. Characters with no transliteration become `?`. Returns the text and
+/// what was replaced.
+#[cfg(feature = "downgrade")]
+pub fn downgrade(s: &str) -> (String, Vec<Replacement>) {
+    let mut out = String::with_capacity(s.len());
+    let mut replacements = Vec::new();
+    for (offset, c) in s.char_indices() {
+        if lookup(c) != Code::Unencodable {
+            out.push(c);
+            continue;
+        }
+        let replacement = punctuation(c)
+            .or_else(|| deunicode::deunicode_char(c))
+            .filter(|r| r.chars().all(|c| lookup(c) != Code::Unencodable))
+            .unwrap_or("?");
+        out.push_str(replacement);
+        replacements.push(Replacement { offset, original: c, replacement });
+    }
+    (out, replacements)
+}
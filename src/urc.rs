@@ -0,0 +1,164 @@
+//! The unsolicited result codes a modem in PDU mode sends when a message, status report, USSD
+//! response or cell broadcast arrives.
+
+use alloc::string::String;
+
+use crate::hex::from_hex;
+use crate::pack::septet_count;
+use crate::pdu::{parse_deliver_with_smsc, parse_status_report_with_smsc, ucs2, Deliver, Error, StatusReport};
+use crate::user_data::{DefaultAlphabet, UserDataDecoder};
+use crate::Gsm7Error;
+
+// Octets of a cell broadcast page before its content.
+const CBS_HEADER_OCTETS: usize = 6;
+// Group 0x9 of the CBS data coding schemes: text after a UDH.
+const CBS_GROUP_UDH: u8 = 0x9;
+
+/// A USSD response or network-initiated USSD, from `+CUSD`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ussd {
+    /// `<m>`: 0 if no further action is needed, 1 if the network wants a reply, 2 if it ended the
+    /// session, and higher for errors.
+    pub status: u8,
+    pub text: Option<String>,
+    /// The CBS data coding scheme the network sent the text in.
+    pub dcs: Option<u8>,
+}
+
+/// A page of a cell broadcast message, from `+CBM`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CellBroadcast {
+    /// The geographical scope, message code and update number, as sent.
+    pub serial: u16,
+    pub message_id: u16,
+    pub dcs: u8,
+    /// Which page this is, counting from 1, and how many the message has.
+    pub page: u8,
+    pub pages: u8,
+    /// The text, less the CRs that pad out the page.
+    pub text: String,
+}
+
+/// A result code parsed by `parse_urc`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Urc {
+    Deliver(Deliver),
+    StatusReport(StatusReport),
+    Ussd(Ussd),
+    CellBroadcast(CellBroadcast),
+}
+
+// Decodes text in a CBS data coding scheme, as cell broadcasts and USSD carry it. Languages
+// indicated in the text, by dcs 0x10 and 0x11, are left at its start for GSM 7-bit text and
+// dropped for UCS-2, and a UDH, with group 0x9, is dropped. Compressed text is not supported.
+fn cbs_text(dcs: u8, bytes: &[u8]) -> Result<String, Error> {
+    let gsm7 = match dcs >> 4 {
+        0x0 | 0x2 | 0x3 => true,
+        0x1 => match dcs & 0x0F {
+            0x0 => true,
+            0x1 => return Ok(ucs2(bytes.get(2..).ok_or(Gsm7Error::UnexpectedEnd)?)),
+            _ => return Err(Gsm7Error::UnsupportedDcs(dcs).into()),
+        },
+        // The compression bit, which group 0x9 lacks, and the character set.
+        0x4..=0x7 | CBS_GROUP_UDH => match dcs & 0x2C {
+            0x00 => true,
+            0x08 => false,
+            _ => return Err(Gsm7Error::UnsupportedDcs(dcs).into()),
+        },
+        0xF => match dcs & 0x04 {
+            0x00 => true,
+            _ => return Err(Gsm7Error::UnsupportedDcs(dcs).into()),
+        },
+        _ => return Err(Gsm7Error::UnsupportedDcs(dcs).into()),
+    };
+    let udhi = dcs >> 4 == CBS_GROUP_UDH;
+    let mut text = match (gsm7, udhi) {
+        (true, _) => DefaultAlphabet.decode_user_data(0x00, udhi, septet_count(bytes.len()), bytes)?.text,
+        (false, false) => ucs2(bytes),
+        (false, true) => {
+            let udhl = *bytes.first().ok_or(Gsm7Error::UnexpectedEnd)? as usize;
+            ucs2(bytes.get(1 + udhl..).ok_or(Gsm7Error::UnexpectedEnd)?)
+        },
+    };
+    if gsm7 {
+        text.truncate(text.trim_end_matches('\r').len());
+    }
+    Ok(text)
+}
+
+/// Parses a page of a cell broadcast message as `+CBM` gives it in PDU mode: the 88 octets of
+/// 3GPP 23.041, whose content is GSM 7-bit or UCS-2.
+pub fn parse_cell_broadcast(page: &[u8]) -> Result<CellBroadcast, Error> {
+    let header = page.get(..CBS_HEADER_OCTETS).ok_or(Gsm7Error::UnexpectedEnd)?;
+    Ok(CellBroadcast {
+        serial: u16::from_be_bytes([header[0], header[1]]),
+        message_id: u16::from_be_bytes([header[2], header[3]]),
+        dcs: header[4],
+        page: header[5] >> 4,
+        pages: header[5] & 0x0F,
+        text: cbs_text(header[4], &page[CBS_HEADER_OCTETS..])?,
+    })
+}
+
+/// How a modem gives the string of a `+CUSD`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UssdFormat {
+    /// Decoded into the modem's character set, as most modems do by default.
+    Text,
+    /// As the hex of the encoded text, as modems do with `AT+CSCS="HEX"` and some in PDU mode.
+    Hex,
+}
+
+/// Parses the fields after `+CUSD:`: `<m>[,<str>[,<dcs>]]`, with the string given as `format`
+/// says. Hex text is decoded with the DCS, or with DCS 0 if there is none, as 3GPP 27.007 has it.
+pub fn parse_ussd(fields: &str, format: UssdFormat) -> Result<Ussd, Error> {
+    let (status, rest) = fields.split_once(',').unwrap_or((fields, ""));
+    let status = status.trim().parse().map_err(|_| Error::InvalidResultCode)?;
+    let rest = rest.trim();
+    let (text, dcs) = match rest.strip_prefix('"') {
+        Some(quoted) => {
+            let end = quoted.rfind('"').ok_or(Error::InvalidResultCode)?;
+            (Some(&quoted[..end]), quoted[end + 1..].trim_start().strip_prefix(','))
+        },
+        None => (None, None),
+    };
+    let dcs = match dcs {
+        Some(dcs) => Some(dcs.trim().parse().map_err(|_| Error::InvalidResultCode)?),
+        None => None,
+    };
+    let text = match (text, format) {
+        (Some(text), UssdFormat::Hex) => {
+            Some(cbs_text(dcs.unwrap_or(0), &from_hex(text).ok_or(Gsm7Error::InvalidHex)?)?)
+        },
+        (text, UssdFormat::Text) => text.map(String::from),
+        (None, _) => None,
+    };
+    Ok(Ussd { status, text, dcs })
+}
+
+/// Parses a `+CMT`, `+CDS`, `+CUSD` or `+CBM` result code from a modem in PDU mode: the line
+/// with the code, and for all but `+CUSD` the hex PDU on the lines after it. The string of a
+/// `+CUSD` is taken to be in `ussd` format.
+pub fn parse_urc(urc: &str, ussd: UssdFormat) -> Result<Urc, Error> {
+    let urc = urc.trim();
+    let (header, body) = urc.split_once('\n').unwrap_or((urc, ""));
+    let pdu = || from_hex(&body.split_whitespace().collect::<String>()).ok_or(Gsm7Error::InvalidHex);
+    if let Some(fields) = header.strip_prefix("+CUSD:") {
+        return Ok(Urc::Ussd(parse_ussd(fields, ussd)?));
+    }
+    match header.split(':').next() {
+        Some("+CMT") => Ok(Urc::Deliver(parse_deliver_with_smsc(&pdu()?)?)),
+        Some("+CDS") => Ok(Urc::StatusReport(parse_status_report_with_smsc(&pdu()?)?)),
+        Some("+CBM") => Ok(Urc::CellBroadcast(parse_cell_broadcast(&pdu()?)?)),
+        _ => Err(Error::InvalidResultCode),
+    }
+}
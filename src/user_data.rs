@@ -0,0 +1,184 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use crate::charset::{lookup, Code, ESC};
+use crate::pack::Packer;
+use crate::segment::{fill_bits, header_septets};
+use crate::{Chars, Gsm7Error};
+
+/// TP-User-Data as it goes into a PDU.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EncodedUserData {
+    pub dcs: u8,
+    /// Length in septets, including any header.
+    pub udl: usize,
+    /// The header, if any, followed by the packed text.
+    pub user_data: Vec<u8>,
+}
+
+/// TP-User-Data as it comes out of a PDU.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecodedUserData {
+    pub text: String,
+    /// The header's information elements, without the UDHL byte.
+    pub udh: Option<Vec<u8>>,
+}
+
+/// Encodes text as TP-User-Data, for PDU crates that take their user-data codec as a parameter.
+pub trait UserDataEncoder {
+    type Error;
+
+    /// Encodes `text` after a user data header made of the information elements in `udh`.
+    fn encode_user_data(&self, text: &str, udh: Option<&[u8]>) -> Result<EncodedUserData, Self::Error>;
+}
+
+/// Decodes TP-User-Data, for PDU crates that take their user-data codec as a parameter.
+pub trait UserDataDecoder {
+    type Error;
+
+    /// Decodes `user_data`, whose length `udl` is in the units `dcs` implies, splitting off the
+    /// user data header if `udhi` is set.
+    fn decode_user_data(
+        &self,
+        dcs: u8,
+        udhi: bool,
+        udl: usize,
+        user_data: &[u8],
+    ) -> Result<DecodedUserData, Self::Error>;
+}
+
+/// The GSM 7-bit default alphabet as a user-data codec, using DCS 0x00.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DefaultAlphabet;
+
+// Whether `dcs` specifies uncompressed text in the GSM 7-bit default alphabet.
+pub(crate) fn is_gsm7_dcs(dcs: u8) -> bool {
+    match dcs >> 4 {
+        // General data coding, with or without automatic deletion.
+        0x0..=0x7 => dcs & 0x2C == 0,
+        // Message waiting indication, discard or store.
+        0xC | 0xD => true,
+        // Data coding and message class.
+        0xF => dcs & 0x04 == 0,
+        _ => false,
+    }
+}
+
+impl UserDataEncoder for DefaultAlphabet {
+    type Error = Gsm7Error;
+
+    fn encode_user_data(&self, text: &str, udh: Option<&[u8]>) -> Result<EncodedUserData, Gsm7Error> {
+        let mut user_data = Vec::with_capacity(text.len() + udh.map_or(0, |udh| udh.len() + 2));
+        let (mut udl, fill) = match udh {
+            Some(udh) => {
+                let udhl = u8::try_from(udh.len()).map_err(|_| Gsm7Error::BufferTooSmall)?;
+                user_data.push(udhl);
+                user_data.extend_from_slice(udh);
+                (header_septets(udh.len() + 1), fill_bits(udh.len() + 1))
+            },
+            None => (0, 0),
+        };
+        let mut packer = Packer::with_fill(fill);
+        for c in text.chars() {
+            match lookup(c) {
+                Code::Basic(septet) => {
+                    user_data.extend(packer.push(septet));
+                    udl += 1;
+                },
+                Code::Escaped(septet) => {
+                    user_data.extend(packer.push(ESC));
+                    user_data.extend(packer.push(septet));
+                    udl += 2;
+                },
+                Code::Unencodable => return Err(Gsm7Error::Unencodable(c)),
+            }
+        }
+        user_data.extend(packer.finish_zeroed());
+        Ok(EncodedUserData { dcs: 0x00, udl, user_data })
+    }
+}
+
+impl UserDataDecoder for DefaultAlphabet {
+    type Error = Gsm7Error;
+
+    fn decode_user_data(
+        &self,
+        dcs: u8,
+        udhi: bool,
+        udl: usize,
+        user_data: &[u8],
+    ) -> Result<DecodedUserData, Gsm7Error> {
+        if !is_gsm7_dcs(dcs) {
+            return Err(Gsm7Error::UnsupportedDcs(dcs));
+        }
+        let (udh, start) = if udhi {
+            let udhl = *user_data.first().ok_or(Gsm7Error::UnexpectedEnd)? as usize;
+            let udh = user_data.get(1..1 + udhl).ok_or(Gsm7Error::UnexpectedEnd)?;
+            (Some(udh.to_vec()), header_septets(udhl + 1))
+        }
+        else {
+            (None, 0)
+        };
+        if start > udl {
+            return Err(Gsm7Error::UnexpectedEnd);
+        }
+        let text = Chars::with_septet_range(user_data, start, udl).collect::<Result<_, _>>()?;
+        Ok(DecodedUserData { text, udh })
+    }
+}
+
+// Valid information elements for fuzzing: concatenation with 8- and 16-bit references, port
+// addressing with 8- and 16-bit ports, and national language shifts.
+#[cfg(feature = "arbitrary")]
+fn arbitrary_udh(u: &mut arbitrary::Unstructured<'_>) -> arbitrary::Result<Vec<u8>> {
+    let mut udh = Vec::new();
+    for _ in 0..u.int_in_range(1..=3)? {
+        match u.int_in_range(0..=5)? {
+            0 | 1 => {
+                let total = u.int_in_range(1..=255)?;
+                let seq = u.int_in_range(1..=total)?;
+                match u.arbitrary()? {
+                    true => udh.extend_from_slice(&[0x00, 0x03, u.arbitrary()?, total, seq]),
+                    false => udh.extend_from_slice(&[0x08, 0x04, u.arbitrary()?, u.arbitrary()?, total, seq]),
+                }
+            },
+            2 => udh.extend_from_slice(&[0x04, 0x02, u.arbitrary()?, u.arbitrary()?]),
+            3 => udh.extend_from_slice(&[0x05, 0x04, u.arbitrary()?, u.arbitrary()?, u.arbitrary()?, u.arbitrary()?]),
+            iei => udh.extend_from_slice(&[if iei == 4 { 0x24 } else { 0x25 }, 0x01, u.int_in_range(1..=3)?]),
+        }
+    }
+    Ok(udh)
+}
+
+/// GSM 7-bit text encoded with a DCS that says so, after a valid header or none, and short enough
+/// to fit a single message.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for EncodedUserData {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let mut text = crate::Gsm7String::arbitrary(u)?;
+        let udh = if u.arbitrary()? { Some(arbitrary_udh(u)?) } else { None };
+        let header = udh.as_ref().map_or(0, |udh| header_septets(1 + udh.len()));
+        text.truncate_septets(crate::Budget::Single.septets() - header);
+        let mut user_data = DefaultAlphabet
+            .encode_user_data(text.as_str(), udh.as_deref())
+            .map_err(|_| arbitrary::Error::IncorrectFormat)?;
+        // Default alphabet, with no message class or with each of them.
+        user_data.dcs = *u.choose(&[0x00, 0x10, 0x11, 0x12, 0x13, 0xF0, 0xF1, 0xF2, 0xF3])?;
+        Ok(user_data)
+    }
+}
+
+/// GSM 7-bit text, after a valid header or none.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for DecodedUserData {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let text = crate::Gsm7String::arbitrary(u)?.into_string();
+        let udh = if u.arbitrary()? { Some(arbitrary_udh(u)?) } else { None };
+        Ok(Self { text, udh })
+    }
+}
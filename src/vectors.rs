@@ -0,0 +1,186 @@
+//! Known-good encodings for running the same conformance checks against other code.
+//!
+//! The vectors in `VECTORS` use the default alphabet. Decoding `packed` with a UDL of `udl`
+//! yields `text`, and encoding `text` with `padding` yields `packed`. Those in `NATIONAL_VECTORS`
+//! use the national tables of `alphabet`, which `udh` announces, and are synthetic.
+
+use crate::{Alphabet, NationalLanguage, Padding};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Vector {
+    pub name: &'static str,
+    pub text: &'static str,
+    pub udl: usize,
+    pub packed: &'static [u8],
+    pub padding: Padding,
+}
+
+pub const VECTORS: &[Vector] = &[
+    Vector {
+        name: "3GPP 23.038 packing example",
+        text: "hellohello",
+        udl: 10,
+        packed: &[0xE8, 0x32, 0x9B, 0xFD, 0x46, 0x97, 0xD9, 0xEC, 0x37],
+        padding: Padding::Zero,
+    },
+    Vector {
+        name: "short text",
+        text: "Hello",
+        udl: 5,
+        packed: &[0xC8, 0x32, 0x9B, 0xFD, 0x06],
+        padding: Padding::Zero,
+    },
+    Vector {
+        name: "octet-aligned, no padding",
+        text: "12345678",
+        udl: 8,
+        packed: &[0x31, 0xD9, 0x8C, 0x56, 0xB3, 0xDD, 0x70],
+        padding: Padding::Zero,
+    },
+    Vector {
+        name: "seven spare bits filled with CR",
+        text: "1234567",
+        udl: 7,
+        packed: &[0x31, 0xD9, 0x8C, 0x56, 0xB3, 0xDD, 0x1A],
+        padding: Padding::Cr,
+    },
+    Vector {
+        name: "seven spare bits left zero, which reads as '@' without a UDL",
+        text: "1234567",
+        udl: 7,
+        packed: &[0x31, 0xD9, 0x8C, 0x56, 0xB3, 0xDD, 0x00],
+        padding: Padding::Zero,
+    },
+    Vector {
+        name: "escapes with seven spare bits filled with CR",
+        text: "[5€]",
+        udl: 7,
+        packed: &[0x1B, 0x5E, 0x6D, 0x53, 0xDE, 0xF8, 0x1A],
+        padding: Padding::Cr,
+    },
+    Vector {
+        name: "'@' is septet zero",
+        text: "@@@@@@@@",
+        udl: 8,
+        packed: &[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+        padding: Padding::Zero,
+    },
+    Vector {
+        name: "basic table beyond ASCII",
+        text: "Grüße",
+        udl: 5,
+        packed: &[0x47, 0xB9, 0xDF, 0x53, 0x06],
+        padding: Padding::Zero,
+    },
+    Vector {
+        name: "extension table brace",
+        text: "{",
+        udl: 2,
+        packed: &[0x1B, 0x14],
+        padding: Padding::Zero,
+    },
+    Vector {
+        name: "extension table euro sign",
+        text: "€",
+        udl: 2,
+        packed: &[0x9B, 0x32],
+        padding: Padding::Zero,
+    },
+    Vector {
+        name: "extension table form feed",
+        text: "\u{C}",
+        udl: 2,
+        packed: &[0x1B, 0x05],
+        padding: Padding::Zero,
+    },
+];
+
+/// A vector for text in national tables, which the information elements in `udh` announce to the
+/// receiver. `packed` is the text alone, without the header. Decoding `packed` with a UDL of `udl`
+/// with the tables of `alphabet` yields `text`, and encoding `text` with them, padded with zeros,
+/// yields `packed`.
+///
+/// These are synthetic, not captured from a network: `septets` was looked up by hand in the tables
+/// of 3GPP 23.038, annex A, and `packed` is those septets packed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NationalVector {
+    pub name: &'static str,
+    pub text: &'static str,
+    pub alphabet: Alphabet,
+    pub udh: &'static [u8],
+    pub udl: usize,
+    pub septets: &'static [u8],
+    pub packed: &'static [u8],
+}
+
+pub const NATIONAL_VECTORS: &[NationalVector] = &[
+    NationalVector {
+        name: "Turkish locking and single shift tables",
+        text: "Ağaç şişe, İzmir",
+        alphabet: Alphabet {
+            locking_shift: Some(NationalLanguage::Turkish),
+            single_shift: Some(NationalLanguage::Turkish),
+        },
+        udh: &[0x25, 0x01, 0x01, 0x24, 0x01, 0x01],
+        udl: 16,
+        // 'ğ' is 0x0C, 'ç' 0x60, 'ş' 0x1D and 'İ' 0x40 in the locking shift table.
+        septets: &[
+            0x41, 0x0C, 0x61, 0x60, 0x20, 0x1D, 0x69, 0x1D, 0x65, 0x2C, 0x20, 0x40, 0x7A, 0x6D, 0x69, 0x72,
+        ],
+        packed: &[0x41, 0x46, 0x18, 0x0C, 0xEA, 0xA4, 0x3B, 0x65, 0x16, 0x08, 0xA8, 0x6F, 0xA7, 0xE5],
+    },
+    NationalVector {
+        name: "Spanish single shift table, which has no locking shift table",
+        text: "¿Qué tal, Tomás?",
+        alphabet: Alphabet { locking_shift: None, single_shift: Some(NationalLanguage::Spanish) },
+        udh: &[0x24, 0x01, 0x02],
+        udl: 17,
+        // '¿' and 'é' are in the default table, and 'á' is 0x61 in the single shift table.
+        septets: &[
+            0x60, 0x51, 0x75, 0x05, 0x20, 0x74, 0x61, 0x6C, 0x2C, 0x20, 0x54, 0x6F, 0x6D, 0x1B, 0x61, 0x73, 0x3F,
+        ],
+        packed: &[0xE0, 0x68, 0xBD, 0x00, 0xA2, 0x87, 0xD9, 0x2C, 0x10, 0xF5, 0xDD, 0xDE, 0x84, 0xE7, 0x3F],
+    },
+    NationalVector {
+        name: "Portuguese locking and single shift tables",
+        text: "Ação à vista",
+        alphabet: Alphabet {
+            locking_shift: Some(NationalLanguage::Portuguese),
+            single_shift: Some(NationalLanguage::Portuguese),
+        },
+        udh: &[0x25, 0x01, 0x03, 0x24, 0x01, 0x03],
+        udl: 12,
+        // 'ç' is 0x09, 'ã' 0x7B and 'à' 0x7F in the locking shift table.
+        septets: &[0x41, 0x09, 0x7B, 0x6F, 0x20, 0x7F, 0x20, 0x76, 0x69, 0x73, 0x74, 0x61],
+        packed: &[0xC1, 0xC4, 0xFE, 0x0D, 0xFA, 0x83, 0xEC, 0xE9, 0x39, 0x3D, 0x0C],
+    },
+];
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    use super::{NATIONAL_VECTORS, VECTORS};
+    use crate::{Chars, Encoder, Padding};
+
+    #[test]
+    fn vectors_round_trip() {
+        for v in VECTORS {
+            let text = Chars::with_udl(v.packed, v.udl).collect::<Result<String, _>>();
+            assert_eq!(text.as_deref(), Ok(v.text), "{}", v.name);
+
+            let mut encoder = Encoder::new();
+            assert_eq!(encoder.push_str(v.text), Ok(v.text.len()), "{}", v.name);
+            assert_eq!(encoder.finish(v.padding), v.packed, "{}", v.name);
+        }
+
+        for v in NATIONAL_VECTORS {
+            assert_eq!(v.septets.len(), v.udl, "{}", v.name);
+            assert_eq!(crate::pack_septets(v.septets), v.packed, "{}", v.name);
+            assert_eq!(v.alphabet.decode(v.packed, Some(v.udl)).as_deref(), Ok(v.text), "{}", v.name);
+            assert_eq!(v.alphabet.encode(v.text, Padding::Zero).as_deref(), Ok(v.packed), "{}", v.name);
+            assert_eq!(v.alphabet.header_elements().flatten().collect::<Vec<_>>(), v.udh, "{}", v.name);
+        }
+    }
+}
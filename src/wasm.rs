@@ -0,0 +1,75 @@
+//! JavaScript bindings, for use from a `cdylib` crate that depends on this one with the `wasm`
+//! feature enabled.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use wasm_bindgen::prelude::*;
+
+use crate::{Chars, Gsm7Error, Gsm7String};
+
+fn js_error(e: Gsm7Error) -> JsError {
+    JsError::new(&e.to_string())
+}
+
+/// Encodes `text`, returning the packed bytes as a `Uint8Array`.
+#[wasm_bindgen(js_name = encode)]
+pub fn encode_js(text: &str) -> Result<Vec<u8>, JsError> {
+    crate::encode(text).map_err(js_error)
+}
+
+/// Decodes packed bytes, reading exactly `udl` septets if given.
+#[wasm_bindgen(js_name = decode)]
+pub fn decode_js(bytes: &[u8], udl: Option<usize>) -> Result<String, JsError> {
+    let chars = match udl {
+        Some(udl) => Chars::with_udl(bytes, udl),
+        None => Chars::new(bytes),
+    };
+    chars.collect::<Result<_, _>>().map_err(js_error)
+}
+
+/// Character and septet counts for `text`.
+#[wasm_bindgen]
+pub fn analyze(text: &str) -> Analysis {
+    Analysis {
+        characters: text.chars().count(),
+        septets: text.parse::<Gsm7String>().ok().map(|s| s.septet_len()),
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct Analysis {
+    characters: usize,
+    septets: Option<usize>,
+}
+
+#[wasm_bindgen]
+impl Analysis {
+    #[wasm_bindgen(getter)]
+    pub fn characters(&self) -> usize {
+        self.characters
+    }
+
+    /// Encoded length in septets, or `undefined` if the text cannot be encoded.
+    #[wasm_bindgen(getter)]
+    pub fn septets(&self) -> Option<usize> {
+        self.septets
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn encodable(&self) -> bool {
+        self.septets.is_some()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    #[test]
+    fn analyze_counts_septets() {
+        let analysis = super::analyze("[5€]");
+        assert_eq!(analysis.characters(), 4);
+        assert_eq!(analysis.septets(), Some(7));
+        assert!(!super::analyze("ú").encodable());
+    }
+}
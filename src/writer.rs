@@ -0,0 +1,408 @@
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::{fmt, io};
+
+use bitstream_io::{BitWrite, BitWriter, Endianness, LittleEndian, Numeric};
+
+use crate::charset::{lookup, Code, ASCII_IDENTITY};
+use crate::transliterate::LineEndings;
+use crate::{Budget, Gsm7Error, LineEnding, Padding};
+
+const CR: u8 = 0x0D;
+
+/// Encoder onto any bit writer, so that GSM 7-bit text can be written into the middle of a larger
+/// bit-level structure and the bit writer taken back afterwards with `into_bit_writer`. Most
+/// callers want `Gsm7Writer`, which writes to an `io::Write`.
+pub struct Gsm7BitWriter<B: BitWrite> {
+    writer: B,
+    // Bits written in the current message. Only its value modulo 8 matters to `into_writer`, so
+    // wrapping on overflow is harmless there.
+    counter: usize,
+    // The budget given to `with_capacity_septets`, and the septets left in it.
+    capacity: Option<usize>,
+    remaining: Option<usize>,
+    ends_with_cr: bool,
+    line_endings: LineEndings,
+    // Whether the bit writer packs the most significant bit first, which decides how runs of
+    // septets are put together, or `None` if that isn't known and they are written one by one.
+    msb_first: Option<bool>,
+    backfill_udl: Option<Backfill<B>>,
+    // Whether the current message has a UDL byte reserved that `finish` hasn't filled in yet.
+    udl_reserved: bool,
+    replace: Option<Replace>,
+}
+
+// Fills in the UDL byte reserved by `with_udl_prefix`, given the UDL and the bytes written since.
+type Backfill<B> = fn(&mut B, u8, usize) -> io::Result<()>;
+
+// Gives the text to write in place of a character with no GSM 7-bit encoding.
+type Replace = Box<dyn FnMut(char) -> Option<Cow<'static, str>> + Send>;
+
+/// Encoder onto an `io::Write`. Septets are packed least significant bit first, as GSM packs
+/// them, unless `E` is `BigEndian`, which some stacks use.
+pub type Gsm7Writer<W, E = LittleEndian> = Gsm7BitWriter<BitWriter<W, E>>;
+
+impl<W: io::Write> Gsm7Writer<W> {
+    pub fn new(writer: W) -> Self {
+        Self { msb_first: Some(false), ..Self::from(BitWriter::new(writer)) }
+    }
+
+    /// Creates a writer that accepts at most `septets` septets of text, such as 160 for a single
+    /// SMS. A character that would take the text past the budget is not written, and fails with
+    /// an `io::Error` whose source is `Gsm7Error::WouldOverflow`.
+    pub fn with_capacity_septets(writer: W, septets: usize) -> Self {
+        Self { capacity: Some(septets), remaining: Some(septets), ..Self::new(writer) }
+    }
+
+    /// Creates a writer that carries on `bit_offset` bits into a message, as when appending text
+    /// to a partly written buffer or after binary content that ends mid-byte. `writer` should be
+    /// positioned after the whole bytes written so far, and the low `bit_offset % 8` bits of
+    /// `last_byte` are those already written to the byte in progress, which is written again in
+    /// full. Padding, and the length `finish` returns, count from the start of the message.
+    pub fn resume(writer: W, bit_offset: usize, last_byte: u8) -> io::Result<Self> {
+        let mut this = Self::new(writer);
+        let partial = (bit_offset % 8) as u32;
+        if partial > 0 {
+            this.writer.write(partial, last_byte & ((1 << partial) - 1))?;
+        }
+        this.counter = bit_offset;
+        Ok(this)
+    }
+
+    /// Creates a writer that accepts at most the septets `budget` allows.
+    pub fn with_budget(writer: W, budget: Budget) -> Self {
+        Self::with_capacity_septets(writer, budget.septets())
+    }
+}
+
+impl<W: io::Write + io::Seek> Gsm7Writer<W> {
+    /// Creates a writer that reserves a byte ahead of the message for its TP-UDL, and seeks back
+    /// to fill it in with the septets written when the message is finished by `finish` or
+    /// `into_writer`, giving the UDL and UD together as a PDU has them. Each message started by
+    /// `start_new_message` gets one too; `finish` doesn't count it in the length.
+    pub fn with_udl_prefix(mut writer: W) -> io::Result<Self> {
+        writer.write_all(&[0])?;
+        Ok(Self { backfill_udl: Some(backfill_udl::<W, LittleEndian>), udl_reserved: true, ..Self::new(writer) })
+    }
+}
+
+// Writes `udl` to the byte before the last `len` bytes of the sink, and goes back to the end.
+fn backfill_udl<W, E>(writer: &mut BitWriter<W, E>, udl: u8, len: usize) -> io::Result<()>
+where
+    W: io::Write + io::Seek,
+    E: Endianness
+{
+    if let Some(writer) = writer.writer() {
+        writer.seek(io::SeekFrom::Current(-(len as i64) - 1))?;
+        writer.write_all(&[udl])?;
+        writer.seek(io::SeekFrom::Current(len as i64))?;
+    }
+    Ok(())
+}
+
+impl<W: io::Write, E: Endianness> Gsm7Writer<W, E> {
+    /// Creates a writer that packs septets in `endian`'s bit order, one at a time, as
+    /// `with_bit_writer` does.
+    pub fn endian(writer: W, endian: E) -> Self {
+        Self::from(BitWriter::endian(writer, endian))
+    }
+
+    /// Ends the current message without giving up the writer: fills the spare bits of its final
+    /// byte as `padding` says, writes and flushes that byte, and returns the message's length in
+    /// bytes. Unlike `into_writer`, `Padding::Cr` follows 3GPP 23.038 in full.
+    pub fn finish(&mut self, padding: Padding) -> io::Result<usize> {
+        let udl = self.udl()?;
+        let spare = (8 - self.counter % 8) % 8;
+        if padding == Padding::Cr && (spare == 7 || spare == 0 && self.ends_with_cr) {
+            self.writer.write(7, CR)?;
+            self.counter += 7;
+        }
+        self.writer.byte_align()?;
+        self.counter = self.counter.div_ceil(8) * 8;
+        if let Some(backfill_udl) = self.backfill_udl.filter(|_| self.udl_reserved) {
+            backfill_udl(&mut self.writer, udl, self.counter / 8)?;
+            self.udl_reserved = false;
+        }
+        if let Some(writer) = self.writer.writer() {
+            writer.flush()?;
+        }
+        Ok(self.counter / 8)
+    }
+
+    pub fn into_writer(mut self) -> io::Result<W> {
+        let udl = self.udl()?;
+        let remainder = self.counter % 8;
+        if remainder == 7 {
+            self.writer.write(7, 0x0D)?;
+        }
+        else if remainder != 0 {
+            self.writer.byte_align()?;
+        }
+        if let Some(backfill_udl) = self.backfill_udl.filter(|_| self.udl_reserved) {
+            backfill_udl(&mut self.writer, udl, self.counter.div_ceil(8))?;
+        }
+        Ok(self.writer.into_writer())
+    }
+
+    // The septets written, counting any fields and fill bits written ahead of the text.
+    fn udl(&self) -> io::Result<u8> {
+        match self.udl_reserved {
+            true => u8::try_from(self.counter.div_ceil(7)).map_err(|_| Gsm7Error::WouldOverflow.into()),
+            false => Ok(0),
+        }
+    }
+}
+
+impl<B: BitWrite> Gsm7BitWriter<B> {
+    /// Creates a writer that starts at `writer`'s current position, which need not be byte aligned.
+    /// `writer` may pack in either bit order, so septets are written to it one at a time.
+    pub fn with_bit_writer(writer: B) -> Self {
+        Self {
+            writer,
+            counter: 0,
+            capacity: None,
+            remaining: None,
+            ends_with_cr: false,
+            line_endings: LineEndings::default(),
+            msb_first: None,
+            backfill_udl: None,
+            udl_reserved: false,
+            replace: None,
+        }
+    }
+
+    /// Sets how `write_str` writes line breaks. A CRLF split between two calls still counts as one
+    /// line break.
+    pub fn line_endings(self, ending: LineEnding) -> Self {
+        Self { line_endings: LineEndings::new(ending), ..self }
+    }
+
+    /// Has `write_char` and `write_str` ask `replace` what to write in place of each character
+    /// with no GSM 7-bit encoding: some text, which may be empty to drop the character, or `None`
+    /// to fail as without it. The replacement is written whole or not at all, and fails if it
+    /// has no encoding itself. `try_write_str` still stops at such characters.
+    pub fn replace_unencodable<F>(self, replace: F) -> Self
+    where
+        F: FnMut(char) -> Option<Cow<'static, str>> + Send + 'static
+    {
+        Self { replace: Some(Box::new(replace)), ..self }
+    }
+
+    pub fn write_bit(&mut self, bit: bool) -> io::Result<()> {
+        self.writer.write_bit(bit)?;
+        self.counter = self.counter.wrapping_add(1);
+        Ok(())
+    }
+
+    /// Writes the low `bits` bits of `value`, failing if `bits` is over 8 or `value` doesn't fit.
+    pub fn write_u8_bits(&mut self, bits: u32, value: u8) -> io::Result<()> {
+        self.write_bits(bits, value)
+    }
+
+    /// Writes the low `bits` bits of `value`, failing if `bits` is over 32 or `value` doesn't
+    /// fit.
+    pub fn write_u32_bits(&mut self, bits: u32, value: u32) -> io::Result<()> {
+        self.write_bits(bits, value)
+    }
+
+    pub fn write_bytes(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.writer.write_bytes(buf)
+    }
+
+    /// Encodes `s`, composing it to NFC first with the `nfc` feature and writing line breaks as
+    /// `line_endings` says, and returns the number of septets written. `write_char` writes
+    /// characters as they are.
+    pub fn write_str(&mut self, s: &str) -> io::Result<usize> {
+        #[cfg(feature = "nfc")]
+        let normalized = crate::transliterate::nfc(s);
+        #[cfg(feature = "nfc")]
+        let s = &*normalized;
+        let converting = self.line_endings.ending != LineEnding::Keep;
+        let mut rest = s;
+        let mut septets = 0;
+        loop {
+            let run = rest
+                .bytes()
+                .take_while(|&b| ASCII_IDENTITY.get(b as usize) == Some(&true))
+                .take_while(|&b| !converting || b != b'\r' && b != b'\n')
+                .count()
+                .min(self.remaining.unwrap_or(usize::MAX));
+            if run > 0 {
+                self.write_ascii_run(&rest.as_bytes()[..run])?;
+                self.line_endings.not_a_line_break();
+                septets += run;
+                rest = &rest[run..];
+            }
+            let mut chars = rest.chars();
+            septets += match chars.next() {
+                Some(c) => match self.line_endings.convert(c) {
+                    Some(line_break) => {
+                        let mut written = 0;
+                        for c in line_break.chars() {
+                            written += self.write_char(c)?;
+                        }
+                        written
+                    },
+                    None => self.write_char(c)?,
+                },
+                None => return Ok(septets),
+            };
+            rest = chars.as_str();
+        }
+    }
+
+    /// Writes as much of `s` as it can, stopping before a character that has no GSM 7-bit
+    /// encoding or that would overflow the budget given to `with_capacity_septets`. Returns the
+    /// length in bytes of the part written, so the rest can go on into another segment. Unlike
+    /// `write_str`, never composes to NFC, so that the length always refers to `s`.
+    pub fn try_write_str(&mut self, s: &str) -> io::Result<usize> {
+        for (i, c) in s.char_indices() {
+            if !self.can_fit(c) {
+                return Ok(i);
+            }
+            self.write_char(c)?;
+        }
+        Ok(s.len())
+    }
+
+    /// Whether `write_char` could write `c` now: it has a GSM 7-bit encoding and, with a budget,
+    /// the septets it takes are left in it.
+    pub fn can_fit(&self, c: char) -> bool {
+        lookup(c).septets().is_some_and(|septets| self.remaining.is_none_or(|remaining| septets <= remaining))
+    }
+
+    /// Encodes `c`, returning the number of septets it took.
+    pub fn write_char(&mut self, c: char) -> io::Result<usize> {
+        let replacement = match self.replace.as_mut() {
+            Some(replace) if lookup(c) == Code::Unencodable => replace(c).ok_or(Gsm7Error::Unencodable(c))?,
+            _ => return self.encode_char(c),
+        };
+        let mut septets = 0;
+        for c in replacement.chars() {
+            septets += lookup(c).septets().ok_or(Gsm7Error::Unencodable(c))?;
+        }
+        if self.remaining.is_some_and(|remaining| septets > remaining) {
+            return Err(Gsm7Error::WouldOverflow.into());
+        }
+        for c in replacement.chars() {
+            self.encode_char(c)?;
+        }
+        Ok(septets)
+    }
+
+    fn encode_char(&mut self, c: char) -> io::Result<usize> {
+        let code = lookup(c);
+        if let (Some(remaining), Some(septets)) = (self.remaining, code.septets()) {
+            if septets > remaining {
+                return Err(Gsm7Error::WouldOverflow.into());
+            }
+        }
+        match code {
+            Code::Basic(b) => {
+                self.take(1);
+                self.writer.write(7, b)?;
+                self.counter = self.counter.wrapping_add(7);
+                self.ends_with_cr = b == CR;
+            },
+            Code::Escaped(b) => self.write_ext(b)?,
+            Code::Unencodable => return Err(Gsm7Error::Unencodable(c).into()),
+        }
+        Ok(code.septets().unwrap_or(0))
+    }
+
+    /// Starts a new message after `finish`, resetting the byte count and the septet budget, and
+    /// reserving a byte for its UDL if the writer was created by `with_udl_prefix`.
+    pub fn start_new_message(&mut self) -> io::Result<()> {
+        if self.backfill_udl.is_some() {
+            self.writer.write(8, 0u8)?;
+            self.udl_reserved = true;
+        }
+        self.counter = 0;
+        self.remaining = self.capacity;
+        self.ends_with_cr = false;
+        Ok(())
+    }
+
+    /// Gives back the bit writer, with any spare bits of the final byte left unwritten.
+    pub fn into_bit_writer(self) -> B {
+        self.writer
+    }
+
+    // Packs up to eight septets per write, for characters whose septet equals their ASCII value,
+    // when the bit order is known.
+    fn write_ascii_run(&mut self, run: &[u8]) -> io::Result<()> {
+        for chunk in run.chunks(8) {
+            let bits = 7 * chunk.len() as u32;
+            self.take(chunk.len());
+            match self.msb_first {
+                Some(msb_first) => {
+                    let word = chunk.iter().enumerate().fold(0u64, |word, (i, &b)| {
+                        let shift = if msb_first { bits - 7 * (i as u32 + 1) } else { 7 * i as u32 };
+                        word | (b as u64) << shift
+                    });
+                    self.writer.write(bits, word)?;
+                },
+                None => chunk.iter().try_for_each(|&b| self.writer.write(7, b))?,
+            }
+            self.counter = self.counter.wrapping_add(bits as usize);
+            self.ends_with_cr = chunk.last() == Some(&CR);
+        }
+        Ok(())
+    }
+
+    // Kept private so that bitstream-io's `Numeric` stays out of the public API.
+    fn write_bits<U: Numeric>(&mut self, bits: u32, value: U) -> io::Result<()> {
+        self.writer.write(bits, value)?;
+        self.counter = self.counter.wrapping_add(bits as usize);
+        Ok(())
+    }
+
+    // Counts septets against the budget, which the caller has checked.
+    fn take(&mut self, septets: usize) {
+        if let Some(remaining) = self.remaining.as_mut() {
+            *remaining -= septets;
+        }
+    }
+
+    fn write_ext(&mut self, b: u8) -> io::Result<()> {
+        self.take(2);
+        self.writer.write(7, 0x1B)?;
+        self.writer.write(7, b)?;
+        self.counter = self.counter.wrapping_add(14);
+        self.ends_with_cr = false;
+        Ok(())
+    }
+}
+
+/// Shows the writer's position and budget, but not the sink.
+impl<B: BitWrite> fmt::Debug for Gsm7BitWriter<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Gsm7Writer")
+            .field("bits", &self.counter)
+            .field("capacity", &self.capacity)
+            .field("remaining", &self.remaining)
+            .field("byte_aligned", &self.writer.byte_aligned())
+            .finish_non_exhaustive()
+    }
+}
+
+/// The same as `with_bit_writer`.
+impl<W: io::Write, E: Endianness> From<BitWriter<W, E>> for Gsm7Writer<W, E> {
+    fn from(writer: BitWriter<W, E>) -> Self {
+        Self::with_bit_writer(writer)
+    }
+}
+
+/// Lets `uwrite!` and `uwriteln!` format straight into the packed stream.
+#[cfg(feature = "ufmt")]
+impl<B: BitWrite> ufmt::uWrite for Gsm7BitWriter<B> {
+    type Error = io::Error;
+
+    fn write_str(&mut self, s: &str) -> io::Result<()> {
+        Gsm7BitWriter::write_str(self, s).map(drop)
+    }
+
+    fn write_char(&mut self, c: char) -> io::Result<()> {
+        Gsm7BitWriter::write_char(self, c).map(drop)
+    }
+}